@@ -0,0 +1,8 @@
+-- Migration: Add owner to tasks
+-- Created: 2024-01-01
+
+-- Les tâches créées avant l'authentification n'ont pas de propriétaire
+-- et ne sont plus visibles par aucun utilisateur.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS owner_id UUID REFERENCES users(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_owner_created_at ON tasks(owner_id, created_at DESC);
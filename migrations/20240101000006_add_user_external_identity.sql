@@ -0,0 +1,12 @@
+-- Migration: Link users to an external OIDC identity
+-- Created: 2024-01-01
+
+-- Les comptes créés via le fournisseur OIDC n'ont pas de mot de passe local
+ALTER TABLE users ALTER COLUMN password_hash DROP NOT NULL;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS external_issuer VARCHAR(255);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS external_subject VARCHAR(255);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_external_identity
+    ON users(external_issuer, external_subject)
+    WHERE external_subject IS NOT NULL;
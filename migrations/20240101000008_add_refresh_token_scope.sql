@@ -0,0 +1,6 @@
+-- Migration: Add granted scope to refresh tokens
+-- Created: 2024-01-01
+
+-- Scope accordé lors de la connexion, conservé lors des rotations.
+-- NULL pour les tokens émis avant l'introduction des scopes (tous les scopes).
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS scope VARCHAR(255);
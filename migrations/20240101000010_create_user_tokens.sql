@@ -0,0 +1,20 @@
+-- Migration: Email verification and single-use user tokens
+-- Created: 2024-01-01
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified_at TIMESTAMPTZ;
+
+-- Tokens à usage unique envoyés par email (réinitialisation du mot de passe,
+-- vérification d'email). Le token lui-même est signé ; cette table garantit
+-- qu'il n'est utilisé qu'une fois.
+CREATE TABLE IF NOT EXISTS user_tokens (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose VARCHAR(50) NOT NULL,
+    -- Adresse concernée par une vérification d'email
+    email VARCHAR(255),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_tokens_user_purpose ON user_tokens(user_id, purpose);
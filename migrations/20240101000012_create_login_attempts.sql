@@ -0,0 +1,12 @@
+-- Migration: Create login_attempts table
+-- Created: 2024-01-01
+
+-- Compteurs d'échecs de connexion, par compte (email) et par adresse IP
+CREATE TABLE IF NOT EXISTS login_attempts (
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('account', 'ip')),
+    key VARCHAR(255) NOT NULL,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ,
+    PRIMARY KEY (kind, key)
+);
@@ -0,0 +1,42 @@
+-- Migration: Workspaces (isolation multi-tenant des tâches)
+-- Created: 2024-01-01
+
+CREATE TABLE IF NOT EXISTS workspaces (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS workspace_members (
+    workspace_id UUID NOT NULL REFERENCES workspaces(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL DEFAULT 'member' CHECK (role IN ('owner', 'member')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (workspace_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspace_members_user_id ON workspace_members(user_id);
+
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces(id) ON DELETE CASCADE;
+
+-- Reprise des données : chaque propriétaire de tâches reçoit un workspace
+-- personnel contenant ses tâches existantes
+INSERT INTO workspaces (name, created_by)
+SELECT 'Personnel', u.id
+FROM users u
+WHERE EXISTS (SELECT 1 FROM tasks t WHERE t.owner_id = u.id AND t.workspace_id IS NULL);
+
+INSERT INTO workspace_members (workspace_id, user_id, role)
+SELECT w.id, w.created_by, 'owner'
+FROM workspaces w
+WHERE w.created_by IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+UPDATE tasks t
+SET workspace_id = w.id
+FROM workspaces w
+WHERE t.workspace_id IS NULL AND w.created_by = t.owner_id;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_workspace_created_at ON tasks(workspace_id, created_at DESC);
@@ -0,0 +1,14 @@
+-- Migration: Create task_shares table
+-- Created: 2024-01-01
+
+-- Partage d'une tâche avec un utilisateur, y compris hors de son workspace
+CREATE TABLE IF NOT EXISTS task_shares (
+    task_id UUID NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    permission VARCHAR(10) NOT NULL CHECK (permission IN ('read', 'write')),
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (task_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_shares_user_id ON task_shares(user_id);
@@ -0,0 +1,8 @@
+-- Migration: Account status managed by administrators
+-- Created: 2024-01-01
+
+-- Un compte désactivé ne peut plus se connecter ni utiliser ses tokens ou clés d'API
+ALTER TABLE users ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMPTZ;
+-- Réinitialisation imposée : la connexion par mot de passe est refusée
+-- jusqu'à ce qu'un nouveau mot de passe soit choisi
+ALTER TABLE users ADD COLUMN IF NOT EXISTS password_reset_required BOOLEAN NOT NULL DEFAULT FALSE;
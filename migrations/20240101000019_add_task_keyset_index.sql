@@ -0,0 +1,7 @@
+-- Migration: Index for cursor pagination of tasks
+-- Created: 2024-01-01
+
+-- Parcours par curseur de GET /tasks sur (created_at, id) dans un workspace ;
+-- couvre aussi les requêtes de idx_tasks_workspace_created_at, qu'il remplace
+CREATE INDEX IF NOT EXISTS idx_tasks_workspace_created_at_id ON tasks(workspace_id, created_at DESC, id DESC);
+DROP INDEX IF EXISTS idx_tasks_workspace_created_at;
@@ -0,0 +1,21 @@
+-- Migration: Full-text search over tasks
+-- Created: 2024-01-01
+
+-- Langue du contenu de la tâche ('fr' ou 'en'), qui choisit la configuration
+-- de recherche (racinisation et mots vides)
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS language TEXT NOT NULL DEFAULT 'fr'
+    CHECK (language IN ('fr', 'en'));
+
+-- Le titre pèse plus que la description dans le classement
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+    CASE language
+        WHEN 'en' THEN
+            setweight(to_tsvector('english', title), 'A')
+            || setweight(to_tsvector('english', COALESCE(description, '')), 'B')
+        ELSE
+            setweight(to_tsvector('french', title), 'A')
+            || setweight(to_tsvector('french', COALESCE(description, '')), 'B')
+    END
+) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_tasks_search_vector ON tasks USING GIN (search_vector);
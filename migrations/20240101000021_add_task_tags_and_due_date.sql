@@ -0,0 +1,10 @@
+-- Migration: Tags and due date on tasks
+-- Created: 2024-01-01
+
+-- Étiquettes normalisées (minuscules, sans doublon) ; le GIN sert `tags @> ARRAY[...]`
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+CREATE INDEX IF NOT EXISTS idx_tasks_tags ON tasks USING GIN (tags);
+
+-- Échéance optionnelle
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS due_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS idx_tasks_due_at ON tasks (due_at) WHERE due_at IS NOT NULL;
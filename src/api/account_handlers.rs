@@ -0,0 +1,531 @@
+use std::sync::Arc;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::api::dto::ApiResponse;
+use crate::audit::AuditEntry;
+use crate::api::session_handlers::revoke_sessions;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::mailer::{Email, Mailer};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::client::ClientInfo;
+use crate::middleware::workspace::OWNER;
+use crate::models::{
+    EmailVerificationConfirmRequest, PasswordResetConfirmRequest, PasswordResetRequest, Task, User,
+};
+use crate::utils::hash::hash_password;
+use crate::utils::token::{sign_payload, verify_payload};
+use crate::utils::validation::validate;
+
+pub const PASSWORD_RESET: &str = "password_reset";
+pub const EMAIL_VERIFICATION: &str = "email_verification";
+
+// Contenu signé des tokens envoyés par email
+#[derive(Serialize, Deserialize)]
+struct UserTokenPayload {
+    jti: Uuid,
+    sub: Uuid,
+    purpose: String,
+    exp: i64,
+}
+
+/// Token à usage unique consommé avec succès
+pub struct ConsumedUserToken {
+    pub user_id: Uuid,
+    pub email: Option<String>,
+}
+
+/// Émet un token signé à usage unique, en invalidant les précédents de même usage
+pub async fn issue_user_token(
+    db: &Database,
+    config: &AppConfig,
+    user_id: Uuid,
+    purpose: &str,
+    email: Option<&str>,
+    expiration: u64,
+) -> AppResult<String> {
+    let jti = Uuid::new_v4();
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expiration as i64);
+
+    let mut tx = db.pool().begin().await?;
+
+    sqlx::query(
+        "UPDATE user_tokens SET used_at = NOW()
+         WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(purpose)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO user_tokens (id, user_id, purpose, email, expires_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(purpose)
+    .bind(email)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    sign_user_token(config.jwt_secret(), jti, user_id, purpose, expires_at)
+}
+
+fn sign_user_token(
+    secret: &str,
+    jti: Uuid,
+    user_id: Uuid,
+    purpose: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<String> {
+    let payload = UserTokenPayload {
+        jti,
+        sub: user_id,
+        purpose: purpose.to_string(),
+        exp: expires_at.timestamp(),
+    };
+    Ok(sign_payload(&payload, secret)?)
+}
+
+// Signature, usage et expiration ; l'usage unique est vérifié en base
+fn verify_user_token(secret: &str, token: &str, purpose: &str) -> Option<UserTokenPayload> {
+    verify_payload::<UserTokenPayload>(token, secret)
+        .filter(|payload| payload.purpose == purpose && payload.exp > chrono::Utc::now().timestamp())
+}
+
+/// Vérifie un token signé et le marque comme utilisé
+pub async fn consume_user_token(
+    db: &Database,
+    config: &AppConfig,
+    token: &str,
+    purpose: &str,
+) -> AppResult<ConsumedUserToken> {
+    let invalid = || AppError::BadRequest("Lien invalide ou expiré".to_string());
+
+    let payload = verify_user_token(config.jwt_secret(), token, purpose).ok_or_else(invalid)?;
+
+    let consumed = sqlx::query_as::<_, (Uuid, Option<String>)>(
+        "UPDATE user_tokens SET used_at = NOW()
+         WHERE id = $1 AND user_id = $2 AND purpose = $3
+           AND used_at IS NULL AND expires_at > NOW()
+         RETURNING user_id, email"
+    )
+    .bind(payload.jti)
+    .bind(payload.sub)
+    .bind(purpose)
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(invalid)?;
+
+    Ok(ConsumedUserToken {
+        user_id: consumed.0,
+        email: consumed.1,
+    })
+}
+
+/// Envoie un lien de vérification pour l'adresse donnée
+pub async fn send_verification_email(
+    db: &Database,
+    config: &AppConfig,
+    mailer: &dyn Mailer,
+    user_id: Uuid,
+    email: &str,
+) -> AppResult<()> {
+    let token = issue_user_token(
+        db,
+        config,
+        user_id,
+        EMAIL_VERIFICATION,
+        Some(email),
+        config.email_verification_expiration,
+    )
+    .await?;
+
+    mailer.send(verification_email(&config.app_base_url, email, &token)).await?;
+
+    Ok(())
+}
+
+fn verification_email(base_url: &str, email: &str, token: &str) -> Email {
+    Email {
+        to: email.to_string(),
+        subject: "Confirmez votre adresse email".to_string(),
+        body: format!(
+            "Pour confirmer votre adresse email, ouvrez le lien suivant :\n\n{}/verify-email?token={}\n",
+            base_url, token
+        ),
+    }
+}
+
+/// Envoie un lien de réinitialisation du mot de passe
+pub async fn send_password_reset_email(
+    db: &Database,
+    config: &AppConfig,
+    mailer: &dyn Mailer,
+    user_id: Uuid,
+    email: &str,
+) -> AppResult<()> {
+    let token = issue_user_token(
+        db,
+        config,
+        user_id,
+        PASSWORD_RESET,
+        None,
+        config.password_reset_expiration,
+    )
+    .await?;
+
+    mailer.send(password_reset_email(&config.app_base_url, email, &token)).await?;
+
+    Ok(())
+}
+
+fn password_reset_email(base_url: &str, email: &str, token: &str) -> Email {
+    Email {
+        to: email.to_string(),
+        subject: "Réinitialisation de votre mot de passe".to_string(),
+        body: format!(
+            "Pour choisir un nouveau mot de passe, ouvrez le lien suivant :\n\n{}/reset-password?token={}\n\n\
+             Si vous n'êtes pas à l'origine de cette demande, ignorez cet email.\n",
+            base_url, token
+        ),
+    }
+}
+
+/// Supprime un compte et ses données
+///
+/// Ses sessions, clés d'API et partages sont supprimés avec lui. Les
+/// workspaces dont il est le seul membre sont supprimés avec leurs tâches ;
+/// s'il était le dernier propriétaire d'un workspace, le membre le plus ancien
+/// en devient propriétaire. Ses tâches dans les workspaces conservés sont
+/// transférées à leur plus ancien propriétaire. Chaque tâche supprimée ou
+/// transférée est inscrite au journal d'audit au nom de `actor`.
+pub async fn delete_account(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    actor: &AuthenticatedUser,
+    client: &ClientInfo,
+) -> AppResult<()> {
+    // Verrouille les membres des workspaces concernés le temps de la succession
+    let workspace_ids = sqlx::query_scalar::<_, Uuid>(
+        "SELECT workspace_id FROM workspace_members
+         WHERE workspace_id IN (SELECT workspace_id FROM workspace_members WHERE user_id = $1)
+         FOR UPDATE"
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let abandoned = sqlx::query_scalar::<_, Uuid>(
+        "SELECT w.id FROM workspaces w
+         WHERE w.id = ANY($2)
+           AND NOT EXISTS (SELECT 1 FROM workspace_members m WHERE m.workspace_id = w.id AND m.user_id <> $1)"
+    )
+    .bind(user_id)
+    .bind(&workspace_ids)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Tâches des workspaces abandonnés et tâches personnelles : supprimées
+    // explicitement plutôt qu'en cascade, pour en garder la trace
+    let deleted = sqlx::query_as::<_, Task>(
+        "DELETE FROM tasks
+         WHERE workspace_id = ANY($2) OR (workspace_id IS NULL AND owner_id = $1)
+         RETURNING id, workspace_id, owner_id, title, description, completed, language, tags, due_at, created_at, updated_at"
+    )
+    .bind(user_id)
+    .bind(&abandoned)
+    .fetch_all(&mut **tx)
+    .await?;
+    for task in &deleted {
+        AuditEntry::new("task.delete")
+            .by(actor)
+            .target("task", task.id)
+            .before(task)?
+            .record(&mut **tx, client)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM workspaces WHERE id = ANY($1)")
+        .bind(&abandoned)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query(
+        "UPDATE workspace_members m SET role = $2
+         FROM (
+             SELECT DISTINCT ON (workspace_id) workspace_id, user_id
+             FROM workspace_members
+             WHERE workspace_id = ANY($3) AND user_id <> $1
+             ORDER BY workspace_id, created_at
+         ) successor
+         WHERE m.workspace_id = successor.workspace_id AND m.user_id = successor.user_id
+           AND NOT EXISTS (
+               SELECT 1 FROM workspace_members o
+               WHERE o.workspace_id = m.workspace_id AND o.role = $2 AND o.user_id <> $1
+           )"
+    )
+    .bind(user_id)
+    .bind(OWNER)
+    .bind(&workspace_ids)
+    .execute(&mut **tx)
+    .await?;
+
+    // Sans ce transfert, la suppression du compte emporterait ses tâches
+    // (`owner_id ... ON DELETE CASCADE`) dans des workspaces toujours utilisés
+    let transferred = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "UPDATE tasks t SET owner_id = heir.user_id
+         FROM (
+             SELECT DISTINCT ON (workspace_id) workspace_id, user_id
+             FROM workspace_members
+             WHERE workspace_id = ANY($2) AND user_id <> $1 AND role = $3
+             ORDER BY workspace_id, created_at
+         ) heir
+         WHERE t.owner_id = $1 AND t.workspace_id = heir.workspace_id
+         RETURNING t.id, t.owner_id"
+    )
+    .bind(user_id)
+    .bind(&workspace_ids)
+    .bind(OWNER)
+    .fetch_all(&mut **tx)
+    .await?;
+    for (task_id, owner_id) in &transferred {
+        AuditEntry::new("task.transfer")
+            .by(actor)
+            .target("task", task_id)
+            .before(&serde_json::json!({ "owner_id": user_id }))?
+            .after(&serde_json::json!({ "owner_id": owner_id }))?
+            .record(&mut **tx, client)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+#[rocket::post("/auth/password-reset", data = "<request>")]
+pub async fn request_password_reset(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    mailer: &State<Arc<dyn Mailer>>,
+    request: Json<PasswordResetRequest>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    let email = request_data.email.trim().to_lowercase();
+
+    // Seuls les comptes locaux (avec mot de passe) peuvent être réinitialisés
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM users WHERE email = $1 AND password_hash IS NOT NULL"
+    )
+    .bind(&email)
+    .fetch_optional(db.pool())
+    .await?;
+
+    if let Some(user_id) = user_id {
+        if let Err(e) = send_password_reset_email(db, config, mailer.as_ref(), user_id, &email).await {
+            tracing::error!(user_id = %user_id, "Erreur lors de l'envoi de l'email de réinitialisation: {}", e);
+        }
+    }
+
+    // Même réponse que le compte existe ou non
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Si un compte correspond à cet email, un lien de réinitialisation a été envoyé".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/password-reset/confirm", data = "<request>")]
+pub async fn confirm_password_reset(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    client: ClientInfo,
+    request: Json<PasswordResetConfirmRequest>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let consumed = consume_user_token(db, config, &request_data.token, PASSWORD_RESET).await?;
+    let password_hash = hash_password(&request_data.password, config)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    sqlx::query(
+        "UPDATE users SET password_hash = $1, password_reset_required = FALSE, updated_at = NOW()
+         WHERE id = $2"
+    )
+    .bind(&password_hash)
+    .bind(consumed.user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    // Déconnecter les autres appareils : les sessions et refresh tokens existants sont révoqués
+    revoke_sessions(&mut tx, consumed.user_id, None, None).await?;
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(consumed.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    AuditEntry::new("auth.password_reset")
+        .by_user(consumed.user_id)
+        .target("user", consumed.user_id)
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Mot de passe réinitialisé".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/verify-email")]
+pub async fn request_email_verification(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    mailer: &State<Arc<dyn Mailer>>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, email, name, password_hash, email_verified_at, created_at, updated_at
+         FROM users
+         WHERE id = $1"
+    )
+    .bind(user.user_id)
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Utilisateur non trouvé".to_string()))?;
+
+    if user.email_verified_at.is_some() {
+        return Err(AppError::Conflict("Adresse email déjà vérifiée".to_string()));
+    }
+
+    send_verification_email(db, config, mailer.as_ref(), user.id, &user.email).await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Email de vérification envoyé".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/verify-email/confirm", data = "<request>")]
+pub async fn confirm_email_verification(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    client: ClientInfo,
+    request: Json<EmailVerificationConfirmRequest>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let consumed = consume_user_token(db, config, &request_data.token, EMAIL_VERIFICATION).await?;
+
+    // Le lien ne vaut que pour l'adresse à laquelle il a été envoyé
+    let result = sqlx::query(
+        "UPDATE users SET email_verified_at = NOW(), updated_at = NOW()
+         WHERE id = $1 AND email = $2"
+    )
+    .bind(consumed.user_id)
+    .bind(&consumed.email)
+    .execute(db.pool())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::BadRequest("Lien invalide ou expiré".to_string()));
+    }
+
+    AuditEntry::new("auth.email_verified")
+        .by_user(consumed.user_id)
+        .target("user", consumed.user_id)
+        .after(&serde_json::json!({ "email": consumed.email }))?
+        .record(db.pool(), &client)
+        .await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Adresse email vérifiée".to_string(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use chrono::{Duration, Utc};
+    use crate::mailer::MemoryMailer;
+
+    const SECRET: &str = "secret-de-test";
+    const BASE_URL: &str = "https://app.example.com";
+
+    // Token du lien contenu dans le dernier email reçu
+    fn link_token(mailer: &MemoryMailer) -> String {
+        let email = mailer.messages().pop().expect("aucun email envoyé");
+        let (_, rest) = email.body.split_once("?token=").expect("lien absent");
+        rest.split_whitespace().next().unwrap().to_string()
+    }
+
+    #[rocket::async_test]
+    async fn password_reset_link_carries_a_reset_token() {
+        let memory = Arc::new(MemoryMailer::default());
+        let mailer: Arc<dyn Mailer> = memory.clone();
+        let (jti, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let token = sign_user_token(SECRET, jti, user_id, PASSWORD_RESET, Utc::now() + Duration::hours(1)).unwrap();
+        mailer.send(password_reset_email(BASE_URL, "a@example.com", &token)).await.unwrap();
+
+        let messages = memory.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].to, "a@example.com");
+        assert!(messages[0].body.contains("https://app.example.com/reset-password?token="));
+
+        let token = link_token(&memory);
+        let payload = verify_user_token(SECRET, &token, PASSWORD_RESET).expect("token refusé");
+        assert_eq!((payload.jti, payload.sub), (jti, user_id));
+        // Un lien de réinitialisation ne vaut pas vérification d'email, et inversement
+        assert!(verify_user_token(SECRET, &token, EMAIL_VERIFICATION).is_none());
+        assert!(verify_user_token("autre-secret", &token, PASSWORD_RESET).is_none());
+    }
+
+    #[rocket::async_test]
+    async fn verification_link_carries_a_verification_token() {
+        let memory = Arc::new(MemoryMailer::default());
+        let mailer: Arc<dyn Mailer> = memory.clone();
+        let user_id = Uuid::new_v4();
+
+        let token =
+            sign_user_token(SECRET, Uuid::new_v4(), user_id, EMAIL_VERIFICATION, Utc::now() + Duration::hours(1))
+                .unwrap();
+        mailer.send(verification_email(BASE_URL, "b@example.com", &token)).await.unwrap();
+
+        let token = link_token(&memory);
+        assert_eq!(verify_user_token(SECRET, &token, EMAIL_VERIFICATION).map(|p| p.sub), Some(user_id));
+        assert!(verify_user_token(SECRET, &token, PASSWORD_RESET).is_none());
+    }
+
+    #[test]
+    fn expired_or_tampered_tokens_are_refused() {
+        let user_id = Uuid::new_v4();
+        let expired =
+            sign_user_token(SECRET, Uuid::new_v4(), user_id, PASSWORD_RESET, Utc::now() - Duration::seconds(1))
+                .unwrap();
+        assert!(verify_user_token(SECRET, &expired, PASSWORD_RESET).is_none());
+
+        let valid =
+            sign_user_token(SECRET, Uuid::new_v4(), user_id, PASSWORD_RESET, Utc::now() + Duration::hours(1)).unwrap();
+        let (payload, signature) = valid.split_once('.').unwrap();
+        let forged = format!("{}x.{}", payload, signature);
+        assert!(verify_user_token(SECRET, &forged, PASSWORD_RESET).is_none());
+    }
+}
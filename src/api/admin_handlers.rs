@@ -0,0 +1,409 @@
+use std::sync::Arc;
+use rocket::http::uri::Origin;
+use rocket::serde::json::Json;
+use rocket::State;
+use uuid::Uuid;
+use crate::api::account_handlers::{delete_account, send_password_reset_email};
+use crate::api::dto::{ApiResponse, Paginated, PaginationParams};
+use crate::api::session_handlers::revoke_sessions;
+use crate::audit::AuditEntry;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::lockout::unlock_account;
+use crate::mailer::{Email, Mailer, MemoryMailer};
+use crate::middleware::client::ClientInfo;
+use crate::middleware::roles::{Admin, RequireRole, ROLES};
+use crate::models::{AdminUserResponse, Task, TaskResponse, UpdateUserRolesRequest, UserWithRoles};
+use crate::utils::query::contains_pattern;
+use crate::utils::validation::parse_id;
+
+const USER_WITH_ROLES_COLUMNS: &str =
+    "u.id, u.email, u.name, u.password_hash, u.email_verified_at, u.created_at, u.updated_at,
+     u.disabled_at, u.password_reset_required,
+     COALESCE(array_agg(r.role ORDER BY r.role) FILTER (WHERE r.role IS NOT NULL), '{}') AS roles";
+
+fn user_not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Utilisateur avec l'id {} non trouvé", id))
+}
+
+// Un administrateur ne peut ni se désactiver ni se supprimer lui-même
+fn ensure_not_self(admin: &RequireRole<Admin>, id: Uuid) -> AppResult<()> {
+    if admin.user.user_id == id {
+        return Err(AppError::BadRequest(
+            "Cette action ne peut pas viser votre propre compte".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Ferme toutes les sessions de l'utilisateur et révoque ses refresh tokens
+async fn sign_out_everywhere(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, user_id: Uuid) -> AppResult<()> {
+    revoke_sessions(tx, user_id, None, None).await?;
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Liste les utilisateurs et leurs rôles
+///
+/// `q` filtre sur l'email ou le nom (sous-chaîne, insensible à la casse),
+/// `disabled` sur le statut du compte.
+#[rocket::get("/admin/users?<page>&<limit>&<q>&<disabled>")]
+pub async fn list_users(
+    db: &State<Database>,
+    _admin: RequireRole<Admin>,
+    uri: &Origin<'_>,
+    page: Option<u64>,
+    limit: Option<u64>,
+    q: Option<&str>,
+    disabled: Option<bool>,
+) -> AppResult<Paginated<AdminUserResponse>> {
+    let params = PaginationParams { page, limit };
+    let pattern = contains_pattern(q);
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*)
+         FROM users u
+         WHERE ($1::TEXT IS NULL OR u.email ILIKE $1 OR u.name ILIKE $1)
+           AND ($2::BOOLEAN IS NULL OR (u.disabled_at IS NOT NULL) = $2)"
+    )
+    .bind(&pattern)
+    .bind(disabled)
+    .fetch_one(db.pool())
+    .await?;
+
+    let users = sqlx::query_as::<_, UserWithRoles>(&format!(
+        "SELECT {}
+         FROM users u
+         LEFT JOIN user_roles r ON r.user_id = u.id
+         WHERE ($1::TEXT IS NULL OR u.email ILIKE $1 OR u.name ILIKE $1)
+           AND ($2::BOOLEAN IS NULL OR (u.disabled_at IS NOT NULL) = $2)
+         GROUP BY u.id
+         ORDER BY u.created_at DESC
+         LIMIT $3 OFFSET $4",
+        USER_WITH_ROLES_COLUMNS
+    ))
+    .bind(pattern)
+    .bind(disabled)
+    .bind(params.limit() as i64)
+    .bind(params.offset() as i64)
+    .fetch_all(db.pool())
+    .await?;
+
+    let responses: Vec<AdminUserResponse> = users.into_iter().map(AdminUserResponse::from).collect();
+    Ok(Paginated::new(responses, total as u64, &params, uri))
+}
+
+#[rocket::get("/admin/users/<id>")]
+pub async fn get_user(
+    db: &State<Database>,
+    _admin: RequireRole<Admin>,
+    id: &str,
+) -> AppResult<Json<ApiResponse<AdminUserResponse>>> {
+    let id = parse_id(id)?;
+
+    let user = sqlx::query_as::<_, UserWithRoles>(&format!(
+        "SELECT {}
+         FROM users u
+         LEFT JOIN user_roles r ON r.user_id = u.id
+         WHERE u.id = $1
+         GROUP BY u.id",
+        USER_WITH_ROLES_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| user_not_found(id))?;
+
+    Ok(Json(ApiResponse::success(AdminUserResponse::from(user))))
+}
+
+/// Désactive un compte : connexion refusée, sessions fermées, tokens et clés d'API rejetés
+#[rocket::post("/admin/users/<id>/disable")]
+pub async fn disable_user(
+    db: &State<Database>,
+    admin: RequireRole<Admin>,
+    client: ClientInfo,
+    id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let id = parse_id(id)?;
+    ensure_not_self(&admin, id)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let exists = sqlx::query_scalar::<_, bool>(
+        "UPDATE users SET disabled_at = COALESCE(disabled_at, NOW()), updated_at = NOW()
+         WHERE id = $1
+         RETURNING TRUE"
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .is_some();
+    if !exists {
+        return Err(user_not_found(id));
+    }
+
+    sign_out_everywhere(&mut tx, id).await?;
+    AuditEntry::new("admin.user_disable")
+        .by(&admin.user)
+        .target("user", id)
+        .record(&mut *tx, &client)
+        .await?;
+    tx.commit().await?;
+    tracing::info!(user_id = %id, admin_id = %admin.user.user_id, "Compte désactivé");
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Compte désactivé".to_string(),
+    )))
+}
+
+#[rocket::post("/admin/users/<id>/enable")]
+pub async fn enable_user(
+    db: &State<Database>,
+    admin: RequireRole<Admin>,
+    client: ClientInfo,
+    id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let id = parse_id(id)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let result = sqlx::query("UPDATE users SET disabled_at = NULL, updated_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(user_not_found(id));
+    }
+
+    AuditEntry::new("admin.user_enable")
+        .by(&admin.user)
+        .target("user", id)
+        .record(&mut *tx, &client)
+        .await?;
+    tx.commit().await?;
+    tracing::info!(user_id = %id, admin_id = %admin.user.user_id, "Compte réactivé");
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Compte réactivé".to_string(),
+    )))
+}
+
+/// Impose un nouveau mot de passe : sessions fermées, connexion par mot de
+/// passe refusée jusqu'à la réinitialisation via le lien envoyé par email
+#[rocket::post("/admin/users/<id>/password-reset")]
+pub async fn force_password_reset(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    mailer: &State<Arc<dyn Mailer>>,
+    admin: RequireRole<Admin>,
+    client: ClientInfo,
+    id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let id = parse_id(id)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let (email, has_password) = sqlx::query_as::<_, (String, bool)>(
+        "SELECT email, password_hash IS NOT NULL FROM users WHERE id = $1 FOR UPDATE"
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| user_not_found(id))?;
+    // Les comptes externes (OIDC) n'ont pas de mot de passe local
+    if !has_password {
+        return Err(AppError::BadRequest(
+            "Ce compte se connecte via le fournisseur d'identité externe".to_string(),
+        ));
+    }
+
+    sqlx::query("UPDATE users SET password_reset_required = TRUE, updated_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sign_out_everywhere(&mut tx, id).await?;
+    AuditEntry::new("admin.user_password_reset")
+        .by(&admin.user)
+        .target("user", id)
+        .record(&mut *tx, &client)
+        .await?;
+    tx.commit().await?;
+
+    send_password_reset_email(db, config, mailer.as_ref(), id, &email).await?;
+    tracing::info!(user_id = %id, admin_id = %admin.user.user_id, "Réinitialisation du mot de passe imposée");
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Réinitialisation imposée, lien envoyé par email".to_string(),
+    )))
+}
+
+/// Supprime un compte et ses données (voir `delete_account`)
+#[rocket::delete("/admin/users/<id>")]
+pub async fn delete_user(
+    db: &State<Database>,
+    admin: RequireRole<Admin>,
+    client: ClientInfo,
+    id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let id = parse_id(id)?;
+    ensure_not_self(&admin, id)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let (email, name) = sqlx::query_as::<_, (String, String)>("SELECT email, name FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| user_not_found(id))?;
+
+    delete_account(&mut tx, id, &admin.user, &client).await?;
+
+    AuditEntry::new("admin.user_delete")
+        .by(&admin.user)
+        .target("user", id)
+        .before(&serde_json::json!({ "email": email, "name": name }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+    tracing::info!(user_id = %id, admin_id = %admin.user.user_id, "Compte supprimé");
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Compte supprimé".to_string(),
+    )))
+}
+
+#[rocket::put("/admin/users/<id>/roles", data = "<request>")]
+pub async fn update_user_roles(
+    db: &State<Database>,
+    admin: RequireRole<Admin>,
+    client: ClientInfo,
+    id: &str,
+    request: Json<UpdateUserRolesRequest>,
+) -> AppResult<Json<ApiResponse<Vec<String>>>> {
+    let id = parse_id(id)?;
+
+    let mut roles = request.into_inner().roles;
+    roles.sort();
+    roles.dedup();
+    if let Some(unknown) = roles.iter().find(|role| !ROLES.contains(&role.as_str())) {
+        return Err(AppError::Validation(format!("Rôle inconnu: {}", unknown)));
+    }
+
+    let mut tx = db.pool().begin().await?;
+
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !exists {
+        return Err(user_not_found(id));
+    }
+
+    let mut previous = sqlx::query_scalar::<_, String>(
+        "DELETE FROM user_roles WHERE user_id = $1 RETURNING role"
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    previous.sort();
+    sqlx::query("INSERT INTO user_roles (user_id, role) SELECT $1, UNNEST($2::VARCHAR[])")
+        .bind(id)
+        .bind(&roles)
+        .execute(&mut *tx)
+        .await?;
+
+    AuditEntry::new("admin.user_roles_update")
+        .by(&admin.user)
+        .target("user", id)
+        .before(&previous)?
+        .after(&roles)?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        roles,
+        "Rôles mis à jour (effectifs à la prochaine émission de token)".to_string(),
+    )))
+}
+
+#[rocket::delete("/admin/users/<id>/lockout")]
+pub async fn unlock_user(
+    db: &State<Database>,
+    admin: RequireRole<Admin>,
+    client: ClientInfo,
+    id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let id = parse_id(id)?;
+
+    let email = sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(db.pool())
+        .await?
+        .ok_or_else(|| user_not_found(id))?;
+
+    unlock_account(db, &email).await?;
+    AuditEntry::new("admin.user_unlock")
+        .by(&admin.user)
+        .target("user", id)
+        .record(db.pool(), &client)
+        .await?;
+    tracing::info!(user_id = %id, admin_id = %admin.user.user_id, "Verrouillage de connexion levé");
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Compte déverrouillé".to_string(),
+    )))
+}
+
+#[rocket::get("/admin/tasks?<page>&<limit>")]
+pub async fn list_all_tasks(
+    db: &State<Database>,
+    _admin: RequireRole<Admin>,
+    uri: &Origin<'_>,
+    page: Option<u64>,
+    limit: Option<u64>,
+) -> AppResult<Paginated<TaskResponse>> {
+    let params = PaginationParams { page, limit };
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM tasks")
+        .fetch_one(db.pool())
+        .await?;
+
+    let tasks = sqlx::query_as::<_, Task>(
+        "SELECT id, workspace_id, owner_id, title, description, completed, language, tags, due_at, created_at, updated_at 
+         FROM tasks 
+         ORDER BY created_at DESC 
+         LIMIT $1 OFFSET $2"
+    )
+    .bind(params.limit() as i64)
+    .bind(params.offset() as i64)
+    .fetch_all(db.pool())
+    .await?;
+
+    let responses: Vec<TaskResponse> = tasks.into_iter().map(TaskResponse::from).collect();
+    Ok(Paginated::new(responses, total as u64, &params, uri))
+}
+
+/// Emails conservés par le transport `memory` (développement local)
+#[rocket::get("/admin/emails")]
+pub async fn list_sent_emails(
+    _admin: RequireRole<Admin>,
+    memory_mailer: Option<&State<Arc<MemoryMailer>>>,
+) -> AppResult<Json<ApiResponse<Vec<Email>>>> {
+    let memory_mailer = memory_mailer.ok_or_else(|| {
+        AppError::NotFound("Emails consultables uniquement avec MAIL_TRANSPORT=memory".to_string())
+    })?;
+
+    Ok(Json(ApiResponse::success(memory_mailer.messages())))
+}
@@ -0,0 +1,172 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use uuid::Uuid;
+use crate::api::dto::ApiResponse;
+use crate::audit::AuditEntry;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::client::ClientInfo;
+use crate::middleware::scopes::resolve_scope;
+use crate::models::{ApiKey, ApiKeyResponse, CreateApiKeyRequest, CreatedApiKeyResponse};
+use crate::utils::token::{generate_opaque_token, hash_token};
+use crate::utils::validation::{parse_id, validate};
+
+/// Préfixe des clés d'API, pour les reconnaître (ex. dans un scanner de secrets)
+const API_KEY_PREFIX: &str = "rk_";
+
+/// Longueur du début de clé conservé en clair pour l'affichage
+const DISPLAYED_PREFIX_LEN: usize = 10;
+
+// Une clé ne reçoit jamais plus de droits que le token qui la crée : un token
+// limité à `tasks:read` ne peut pas obtenir, sans expiration, `tasks:write`
+fn key_scope(user: &AuthenticatedUser, requested: Option<&str>) -> AppResult<String> {
+    resolve_scope(requested, &user.claims.scope)
+}
+
+#[rocket::post("/api-keys", data = "<request>")]
+pub async fn create_api_key(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<CreateApiKeyRequest>,
+) -> AppResult<Json<ApiResponse<CreatedApiKeyResponse>>> {
+    user.require_interactive("des clés d'API")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    let scope = key_scope(&user, request_data.scope.as_deref())?;
+
+    let api_key = format!("{}{}", API_KEY_PREFIX, generate_opaque_token());
+    let key_prefix: String = api_key.chars().take(DISPLAYED_PREFIX_LEN).collect();
+
+    let mut tx = db.pool().begin().await?;
+
+    let key = sqlx::query_as::<_, ApiKey>(
+        "INSERT INTO api_keys (id, user_id, name, key_prefix, key_hash, scope)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, name, key_prefix, scope, created_at, last_used_at, revoked_at"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user.user_id)
+    .bind(&request_data.name)
+    .bind(&key_prefix)
+    .bind(hash_token(&api_key))
+    .bind(&scope)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    AuditEntry::new("api_key.create")
+        .by(&user)
+        .target("api_key", key.id)
+        .after(&serde_json::json!({ "name": key.name, "scope": key.scope }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        CreatedApiKeyResponse {
+            key: ApiKeyResponse::from(key),
+            api_key,
+        },
+        "Clé d'API créée : conservez-la, elle ne sera plus affichée".to_string(),
+    )))
+}
+
+#[rocket::get("/api-keys")]
+pub async fn list_api_keys(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<ApiResponse<Vec<ApiKeyResponse>>>> {
+    user.require_interactive("des clés d'API")?;
+
+    let keys = sqlx::query_as::<_, ApiKey>(
+        "SELECT id, name, key_prefix, scope, created_at, last_used_at, revoked_at
+         FROM api_keys
+         WHERE user_id = $1
+         ORDER BY created_at DESC"
+    )
+    .bind(user.user_id)
+    .fetch_all(db.pool())
+    .await?;
+
+    let responses: Vec<ApiKeyResponse> = keys.into_iter().map(ApiKeyResponse::from).collect();
+    Ok(Json(ApiResponse::success(responses)))
+}
+
+#[rocket::delete("/api-keys/<id>")]
+pub async fn revoke_api_key(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    user.require_interactive("des clés d'API")?;
+    let id = parse_id(id)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE api_keys SET revoked_at = NOW()
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(id)
+    .bind(user.user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Clé d'API avec l'id {} non trouvée", id)));
+    }
+
+    AuditEntry::new("api_key.revoke")
+        .by(&user)
+        .target("api_key", id)
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Clé d'API révoquée".to_string(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::auth::Claims;
+
+    fn user_with_scope(scope: &str) -> AuthenticatedUser {
+        AuthenticatedUser {
+            user_id: Uuid::nil(),
+            claims: Claims {
+                sub: Uuid::nil().to_string(),
+                exp: 0,
+                iat: 0,
+                jti: "test".to_string(),
+                roles: Vec::new(),
+                scope: scope.to_string(),
+                sid: None,
+            },
+            api_key_id: None,
+        }
+    }
+
+    #[test]
+    fn read_only_token_cannot_mint_write_key() {
+        let user = user_with_scope("tasks:read");
+        assert_eq!(key_scope(&user, None).unwrap(), "tasks:read");
+        assert_eq!(key_scope(&user, Some("tasks:read")).unwrap(), "tasks:read");
+        assert!(key_scope(&user, Some("tasks:write")).is_err());
+        assert!(key_scope(&user, Some("tasks:read tasks:write")).is_err());
+    }
+
+    #[test]
+    fn full_token_can_mint_any_key() {
+        let user = user_with_scope("tasks:read tasks:write");
+        assert_eq!(key_scope(&user, None).unwrap(), "tasks:read tasks:write");
+        assert_eq!(key_scope(&user, Some("tasks:write")).unwrap(), "tasks:write");
+    }
+}
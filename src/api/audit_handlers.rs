@@ -0,0 +1,75 @@
+use rocket::http::uri::Origin;
+use rocket::State;
+use crate::api::dto::{Paginated, PaginationParams};
+use crate::database::Database;
+use crate::errors::AppResult;
+use crate::middleware::roles::{Admin, RequireRole};
+use crate::models::AuditEvent;
+use crate::utils::query::parse_timestamp;
+use crate::utils::validation::parse_id;
+
+/// Consulte le journal d'audit, du plus récent au plus ancien
+///
+/// Filtres combinables : auteur (`actor`), cible (`target_type`, `target_id`),
+/// action et intervalle `[from, to[`.
+#[rocket::get("/admin/audit-events?<page>&<limit>&<actor>&<target_type>&<target_id>&<action>&<from>&<to>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn list_audit_events(
+    db: &State<Database>,
+    _admin: RequireRole<Admin>,
+    uri: &Origin<'_>,
+    page: Option<u64>,
+    limit: Option<u64>,
+    actor: Option<&str>,
+    target_type: Option<&str>,
+    target_id: Option<&str>,
+    action: Option<&str>,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> AppResult<Paginated<AuditEvent>> {
+    let params = PaginationParams { page, limit };
+    let actor = actor
+        .map(parse_id)
+        .transpose()?;
+    let from = parse_timestamp("from", from)?;
+    let to = parse_timestamp("to", to)?;
+
+    let filters = "($1::UUID IS NULL OR actor_id = $1)
+           AND ($2::TEXT IS NULL OR target_type = $2)
+           AND ($3::TEXT IS NULL OR target_id = $3)
+           AND ($4::TEXT IS NULL OR action = $4)
+           AND ($5::TIMESTAMPTZ IS NULL OR occurred_at >= $5)
+           AND ($6::TIMESTAMPTZ IS NULL OR occurred_at < $6)";
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM audit_events WHERE {}", filters))
+        .bind(actor)
+        .bind(target_type)
+        .bind(target_id)
+        .bind(action)
+        .bind(from)
+        .bind(to)
+        .fetch_one(db.pool())
+        .await?;
+
+    let events = sqlx::query_as::<_, AuditEvent>(&format!(
+        "SELECT id, occurred_at, actor_id, api_key_id, action, target_type, target_id,
+                request_id, ip_address, before, after
+         FROM audit_events
+         WHERE {}
+         ORDER BY occurred_at DESC
+         LIMIT $7 OFFSET $8",
+        filters
+    ))
+    .bind(actor)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(action)
+    .bind(from)
+    .bind(to)
+    .bind(params.limit() as i64)
+    .bind(params.offset() as i64)
+    .fetch_all(db.pool())
+    .await?;
+
+    Ok(Paginated::new(events, total as u64, &params, uri))
+}
@@ -0,0 +1,434 @@
+use std::sync::Arc;
+use jsonwebtoken::jwk::JwkSet;
+use rocket::serde::json::Json;
+use rocket::State;
+use uuid::Uuid;
+use crate::api::account_handlers::send_verification_email;
+use crate::audit::AuditEntry;
+use crate::api::two_factor_handlers::{issue_challenge, load_two_factor};
+use crate::api::dto::ApiResponse;
+use crate::api::session_handlers::{resume_session, revoke_sessions, start_session};
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::lockout::LoginAttempt;
+use crate::mailer::Mailer;
+use crate::middleware::auth::{
+    ensure_account_active, generate_token, load_roles, revoke_token, AuthenticatedUser, TokenGrant,
+};
+use crate::middleware::client::ClientInfo;
+use crate::middleware::jwt::JwtKeys;
+use crate::middleware::scopes::{full_scope, resolve_scope};
+use crate::models::{
+    AuthResponse, CreateUserRequest, LoginRequest, LoginResponse, LogoutRequest, RefreshToken,
+    RefreshTokenRequest, User, UserResponse,
+};
+use crate::utils::hash::{hash_password, needs_rehash, verify_dummy_password, verify_password};
+use crate::utils::token::{generate_opaque_token, hash_token};
+use crate::utils::validation::validate;
+
+#[rocket::post("/auth/register", data = "<request>")]
+pub async fn register(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    keys: &State<JwtKeys>,
+    mailer: &State<Arc<dyn Mailer>>,
+    client: ClientInfo,
+    request: Json<CreateUserRequest>,
+) -> AppResult<Json<ApiResponse<AuthResponse>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    let email = request_data.email.trim().to_lowercase();
+
+    let existing = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_optional(db.pool())
+        .await?;
+    if existing.is_some() {
+        return Err(AppError::Conflict(format!("Un compte existe déjà pour l'email {}", email)));
+    }
+
+    let password_hash = hash_password(&request_data.password, config)?;
+    let id = Uuid::new_v4();
+    let now = chrono::Utc::now();
+
+    let user = sqlx::query_as::<_, User>(
+        "INSERT INTO users (id, email, name, password_hash, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, email, name, password_hash, email_verified_at, created_at, updated_at"
+    )
+    .bind(id)
+    .bind(&email)
+    .bind(&request_data.name)
+    .bind(&password_hash)
+    .bind(now)
+    .bind(now)
+    .fetch_one(db.pool())
+    .await
+    .map_err(|e| match e {
+        // Course entre la vérification et l'insertion
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            AppError::Conflict(format!("Un compte existe déjà pour l'email {}", email))
+        }
+        e => AppError::Database(e),
+    })?;
+
+    // L'échec de l'envoi ne bloque pas l'inscription : l'email peut être redemandé
+    if let Err(e) = send_verification_email(db, config, mailer.as_ref(), user.id, &user.email).await {
+        tracing::error!(user_id = %user.id, "Erreur lors de l'envoi de l'email de vérification: {}", e);
+    }
+
+    AuditEntry::new("auth.register")
+        .by_user(user.id)
+        .target("user", user.id)
+        .after(&UserResponse::from(user.clone()))?
+        .record(db.pool(), &client)
+        .await?;
+
+    let scope = full_scope();
+    let session_id = start_session(db.pool(), user.id, &client, config.refresh_token_expiration).await?;
+    let refresh_token =
+        issue_refresh_token(db.pool(), user.id, session_id, &scope, config.refresh_token_expiration).await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        auth_response(db, config, keys, user, session_id, scope, refresh_token).await?,
+        "Compte créé avec succès".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/login", data = "<request>")]
+pub async fn login(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    keys: &State<JwtKeys>,
+    client: ClientInfo,
+    request: Json<LoginRequest>,
+) -> AppResult<Json<ApiResponse<LoginResponse>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    let email = request_data.email.trim().to_lowercase();
+    let scope = resolve_scope(request_data.scope.as_deref(), &full_scope())?;
+
+    let attempt = LoginAttempt::new(&email, client.ip);
+    attempt.ensure_allowed(db).await?;
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, email, name, password_hash, email_verified_at, created_at, updated_at
+         FROM users
+         WHERE email = $1"
+    )
+    .bind(&email)
+    .fetch_optional(db.pool())
+    .await?;
+
+    // Même message que l'email soit inconnu ou le mot de passe erroné,
+    // pour ne pas révéler l'existence d'un compte
+    let invalid = || AppError::Authentication("Email ou mot de passe incorrect".to_string());
+    let password_ok = match user.as_ref().and_then(|u| u.password_hash.as_deref()) {
+        Some(password_hash) => verify_password(&request_data.password, password_hash)?,
+        // Email inconnu ou compte externe (OIDC) : pas de connexion par mot de passe,
+        // mais une vérification factice pour un temps de réponse identique
+        None => {
+            verify_dummy_password(&request_data.password, config)?;
+            false
+        }
+    };
+    // Les échecs sur un email inconnu sont aussi comptés
+    let user = match user {
+        Some(user) if password_ok => user,
+        other => {
+            let entry = match other {
+                Some(user) => AuditEntry::new("auth.login_failed").target("user", user.id),
+                None => AuditEntry::new("auth.login_failed").target("email", &email),
+            };
+            entry.record(db.pool(), &client).await?;
+            attempt.record_failure(db, config).await?;
+            return Err(invalid());
+        }
+    };
+
+    // Compte désactivé, ou nouveau mot de passe imposé par un administrateur
+    ensure_account_active(db, user.id).await?;
+    let reset_required = sqlx::query_scalar::<_, bool>("SELECT password_reset_required FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(db.pool())
+        .await?;
+    if reset_required {
+        return Err(AppError::Authorization(
+            "Réinitialisation du mot de passe requise : utilisez le lien reçu par email".to_string(),
+        ));
+    }
+
+    if let Some(password_hash) = user.password_hash.as_deref() {
+        if needs_rehash(password_hash, config) {
+            upgrade_password_hash(db, config, user.id, password_hash, &request_data.password).await;
+        }
+    }
+
+    // Double authentification : les tokens ne sont émis qu'après POST /auth/login/2fa
+    if load_two_factor(db.pool(), user.id).await?.totp_enabled_at.is_some() {
+        let challenge = issue_challenge(db, config, user.id, &scope).await?;
+        return Ok(Json(ApiResponse::success_with_message(
+            LoginResponse::TwoFactorRequired(challenge),
+            "Code de vérification requis".to_string(),
+        )));
+    }
+
+    attempt.record_success(db).await?;
+
+    let session_id = start_session(db.pool(), user.id, &client, config.refresh_token_expiration).await?;
+    let refresh_token =
+        issue_refresh_token(db.pool(), user.id, session_id, &scope, config.refresh_token_expiration).await?;
+
+    AuditEntry::new("auth.login")
+        .by_user(user.id)
+        .target("session", session_id)
+        .record(db.pool(), &client)
+        .await?;
+
+    Ok(Json(ApiResponse::success(LoginResponse::Authenticated(
+        auth_response(db, config, keys, user, session_id, scope, refresh_token).await?,
+    ))))
+}
+
+#[rocket::post("/auth/refresh", data = "<request>")]
+pub async fn refresh(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    keys: &State<JwtKeys>,
+    client: ClientInfo,
+    request: Json<RefreshTokenRequest>,
+) -> AppResult<Json<ApiResponse<AuthResponse>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    let invalid = || AppError::Authentication("Refresh token invalide ou expiré".to_string());
+
+    let mut tx = db.pool().begin().await?;
+
+    let stored = sqlx::query_as::<_, RefreshToken>(
+        "SELECT id, user_id, family_id, expires_at, used_at, revoked_at, scope
+         FROM refresh_tokens
+         WHERE token_hash = $1
+         FOR UPDATE"
+    )
+    .bind(hash_token(&request_data.refresh_token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(invalid)?;
+
+    if stored.revoked_at.is_some() {
+        return Err(invalid());
+    }
+
+    // Un token déjà échangé est présenté à nouveau : il a probablement fuité.
+    // On révoque toute la famille pour forcer une nouvelle authentification,
+    // ainsi que la session qu'elle porte : les access tokens déjà émis depuis
+    // la chaîne volée cessent d'être acceptés sans attendre leur expiration.
+    if stored.used_at.is_some() {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW()
+             WHERE family_id = $1 AND revoked_at IS NULL"
+        )
+        .bind(stored.family_id)
+        .execute(&mut *tx)
+        .await?;
+        revoke_sessions(&mut tx, stored.user_id, Some(stored.family_id), None).await?;
+        AuditEntry::new("auth.refresh_token_reused")
+            .by_user(stored.user_id)
+            .target("session", stored.family_id)
+            .record(&mut *tx, &client)
+            .await?;
+        tx.commit().await?;
+
+        tracing::warn!(
+            user_id = %stored.user_id,
+            family_id = %stored.family_id,
+            "Réutilisation d'un refresh token détectée, famille et session révoquées"
+        );
+        return Err(invalid());
+    }
+
+    if stored.expires_at <= chrono::Utc::now() {
+        return Err(invalid());
+    }
+    ensure_account_active(db, stored.user_id).await?;
+
+    let granted_scope = stored.scope.clone().unwrap_or_else(full_scope);
+    let scope = resolve_scope(request_data.scope.as_deref(), &granted_scope)?;
+
+    sqlx::query("UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1")
+        .bind(stored.id)
+        .execute(&mut *tx)
+        .await?;
+
+    // La session de la connexion est prolongée, sauf si elle a été révoquée
+    let session_id = stored.family_id;
+    if !resume_session(&mut *tx, session_id, stored.user_id, &client, config.refresh_token_expiration).await? {
+        return Err(invalid());
+    }
+
+    let refresh_token = issue_refresh_token(
+        &mut *tx,
+        stored.user_id,
+        stored.family_id,
+        &scope,
+        config.refresh_token_expiration,
+    )
+    .await?;
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, email, name, password_hash, email_verified_at, created_at, updated_at
+         FROM users
+         WHERE id = $1"
+    )
+    .bind(stored.user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(invalid)?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success(
+        auth_response(db, config, keys, user, session_id, scope, refresh_token).await?,
+    )))
+}
+
+#[rocket::get("/.well-known/jwks.json")]
+pub fn jwks(keys: &State<JwtKeys>) -> Json<JwkSet> {
+    Json(keys.jwks().clone())
+}
+
+#[rocket::post("/auth/logout", data = "<request>")]
+pub async fn logout(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Option<Json<LogoutRequest>>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    if user.api_key_id.is_some() {
+        return Err(AppError::BadRequest(
+            "Une clé d'API se révoque via DELETE /api/api-keys/{id}".to_string(),
+        ));
+    }
+
+    revoke_token(db, &user.claims, user.user_id).await?;
+
+    // Ferme la session du token et les refresh tokens qui en sont issus
+    if let Some(session_id) = user.claims.sid {
+        let mut tx = db.pool().begin().await?;
+        revoke_sessions(&mut tx, user.user_id, Some(session_id), None).await?;
+        tx.commit().await?;
+    }
+
+    let mut entry = AuditEntry::new("auth.logout").by(&user);
+    if let Some(session_id) = user.claims.sid {
+        entry = entry.target("session", session_id);
+    }
+    entry.record(db.pool(), &client).await?;
+
+    if let Some(refresh_token) = request.and_then(|r| r.into_inner().refresh_token) {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW()
+             WHERE revoked_at IS NULL
+               AND user_id = $1
+               AND family_id = (SELECT family_id FROM refresh_tokens WHERE token_hash = $2)"
+        )
+        .bind(user.user_id)
+        .bind(hash_token(&refresh_token))
+        .execute(db.pool())
+        .await?;
+    }
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Déconnexion effectuée".to_string(),
+    )))
+}
+
+// Remplace une empreinte obsolète (bcrypt, paramètres Argon2 dépassés).
+// Un échec n'empêche pas la connexion : la mise à niveau sera retentée.
+async fn upgrade_password_hash(
+    db: &Database,
+    config: &AppConfig,
+    user_id: Uuid,
+    current_hash: &str,
+    password: &str,
+) {
+    let new_hash = match hash_password(password, config) {
+        Ok(new_hash) => new_hash,
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Erreur lors de la mise à niveau du mot de passe: {}", e);
+            return;
+        }
+    };
+
+    // Condition sur l'ancienne empreinte : un changement concurrent n'est pas écrasé
+    let result = sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2 AND password_hash = $3")
+        .bind(&new_hash)
+        .bind(user_id)
+        .bind(current_hash)
+        .execute(db.pool())
+        .await;
+
+    match result {
+        Ok(_) => tracing::info!(user_id = %user_id, "Empreinte du mot de passe mise à niveau"),
+        Err(e) => tracing::error!(user_id = %user_id, "Erreur lors de la mise à niveau du mot de passe: {}", e),
+    }
+}
+
+// Crée un nouveau refresh token dans la famille donnée et retourne sa valeur en clair
+pub(crate) async fn issue_refresh_token<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    family_id: Uuid,
+    scope: &str,
+    expiration: u64,
+) -> AppResult<String>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let token = generate_opaque_token();
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expiration as i64);
+
+    sqlx::query(
+        "INSERT INTO refresh_tokens (id, user_id, family_id, token_hash, expires_at, scope)
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(family_id)
+    .bind(hash_token(&token))
+    .bind(expires_at)
+    .bind(scope)
+    .execute(executor)
+    .await?;
+
+    Ok(token)
+}
+
+pub(crate) async fn auth_response(
+    db: &Database,
+    config: &AppConfig,
+    keys: &JwtKeys,
+    user: User,
+    session_id: Uuid,
+    scope: String,
+    refresh_token: String,
+) -> AppResult<AuthResponse> {
+    let roles = load_roles(db, user.id).await?;
+    let grant = TokenGrant {
+        roles,
+        scope: scope.clone(),
+        session_id: Some(session_id),
+    };
+    let access_token = generate_token(&user.id.to_string(), grant, keys, config.jwt_expiration)?;
+
+    Ok(AuthResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: config.jwt_expiration,
+        scope,
+        refresh_token,
+        user: UserResponse::from(user),
+    })
+}
@@ -1,3 +1,7 @@
+use rocket::http::uri::Origin;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
 
 // DTOs génériques pour les réponses API
@@ -41,14 +45,147 @@ pub struct PaginationParams {
 }
 
 impl PaginationParams {
+    pub const DEFAULT_LIMIT: u64 = 20;
+    /// Au-delà, `limit` est ramené à ce maximum
+    pub const MAX_LIMIT: u64 = 100;
+
+    /// Numéro de page, à partir de 1
+    pub fn page(&self) -> u64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
     pub fn offset(&self) -> u64 {
-        let page = self.page.unwrap_or(1);
-        let limit = self.limit.unwrap_or(20);
-        (page.saturating_sub(1)) * limit
+        (self.page() - 1).saturating_mul(self.limit()).min(i64::MAX as u64)
     }
 
     pub fn limit(&self) -> u64 {
-        self.limit.unwrap_or(20)
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT).clamp(1, Self::MAX_LIMIT)
+    }
+}
+
+/// Page d'une liste, avec le total et les liens de navigation
+///
+/// Servie dans une `ApiResponse` ; les liens sont repris dans l'en-tête
+/// `Link` (RFC 8288) avec les relations `first`, `prev`, `next` et `last`.
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub page: u64,
+    pub limit: u64,
+    pub total: u64,
+    pub total_pages: u64,
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    #[serde(skip)]
+    first: String,
+    #[serde(skip)]
+    last: String,
+}
+
+impl<T> Paginated<T> {
+    /// `uri` est l'URI de la requête : les liens la reprennent en remplaçant `page` et `limit`
+    pub fn new(items: Vec<T>, total: u64, params: &PaginationParams, uri: &Origin<'_>) -> Self {
+        let page = params.page();
+        let limit = params.limit();
+        let total_pages = total.div_ceil(limit);
+
+        let link = |page: u64| page_uri(uri, page, limit);
+        Self {
+            items,
+            page,
+            limit,
+            total,
+            total_pages,
+            next: (page < total_pages).then(|| link(page + 1)),
+            prev: (page > 1).then(|| link((page - 1).min(total_pages.max(1)))),
+            first: link(1),
+            last: link(total_pages.max(1)),
+        }
     }
+
+    fn link_header(&self) -> String {
+        let mut links = vec![format!("<{}>; rel=\"first\"", self.first)];
+        if let Some(prev) = &self.prev {
+            links.push(format!("<{}>; rel=\"prev\"", prev));
+        }
+        if let Some(next) = &self.next {
+            links.push(format!("<{}>; rel=\"next\"", next));
+        }
+        links.push(format!("<{}>; rel=\"last\"", self.last));
+        links.join(", ")
+    }
+}
+
+// Chemin et paramètres de la requête (encodés tels que reçus), `page` et `limit`
+// remplacés, `cursor` retiré
+fn page_uri(uri: &Origin<'_>, page: u64, limit: u64) -> String {
+    with_query(uri, &["page", "cursor"], &[format!("page={}", page), format!("limit={}", limit)])
 }
 
+// Retire les paramètres `removed` et `limit` de la requête, puis ajoute `added`
+fn with_query(uri: &Origin<'_>, removed: &[&str], added: &[String]) -> String {
+    let mut query: Vec<String> = uri
+        .query()
+        .map(|query| {
+            query
+                .as_str()
+                .split('&')
+                .filter(|pair| {
+                    let name = pair.split('=').next().unwrap_or_default();
+                    !pair.is_empty() && name != "limit" && !removed.contains(&name)
+                })
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    query.extend_from_slice(added);
+
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Paginated<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let link = self.link_header();
+        let mut response = Json(ApiResponse::success(self)).respond_to(request)?;
+        response.set_raw_header("Link", link);
+        Ok(response)
+    }
+}
+
+/// Page d'une liste parcourue par curseur
+///
+/// Pas de total ni de numéro de page : `next_cursor` désigne la position
+/// après le dernier élément et vaut `null` en fin de liste. Le lien `next`
+/// est repris dans l'en-tête `Link`.
+#[derive(Debug, Serialize)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub limit: u64,
+    pub next_cursor: Option<String>,
+    pub next: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    pub fn new(items: Vec<T>, limit: u64, next_cursor: Option<String>, uri: &Origin<'_>) -> Self {
+        let next = next_cursor.as_ref().map(|cursor| {
+            with_query(uri, &["page", "cursor"], &[format!("cursor={}", cursor), format!("limit={}", limit)])
+        });
+        Self {
+            items,
+            limit,
+            next_cursor,
+            next,
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for CursorPage<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let link = self.next.as_ref().map(|next| format!("<{}>; rel=\"next\"", next));
+        let mut response = Json(ApiResponse::success(self)).respond_to(request)?;
+        if let Some(link) = link {
+            response.set_raw_header("Link", link);
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,333 @@
+use std::sync::Arc;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::api::dto::ApiResponse;
+use crate::audit::AuditEntry;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::mailer::{Email, Mailer};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::client::ClientInfo;
+use crate::middleware::workspace::{WorkspaceMember, WORKSPACE_ROLES};
+use crate::models::{
+    CreateInvitationRequest, InvitationResponse, InvitationTokenRequest, WorkspaceInvitation,
+};
+use crate::utils::token::{sign_payload, verify_payload};
+use crate::utils::validation::{parse_id, validate};
+
+const WORKSPACE_INVITATION: &str = "workspace_invitation";
+
+// Contenu signé du lien d'invitation
+#[derive(Serialize, Deserialize)]
+struct InvitationPayload {
+    jti: Uuid,
+    workspace_id: Uuid,
+    purpose: String,
+    exp: i64,
+}
+
+// Vérifie le lien et retourne l'invitation si elle est toujours en attente
+async fn pending_invitation<'e, E>(
+    executor: E,
+    config: &AppConfig,
+    token: &str,
+) -> AppResult<WorkspaceInvitation>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let invalid = || AppError::BadRequest("Invitation invalide ou expirée".to_string());
+
+    let payload = verify_payload::<InvitationPayload>(token, config.jwt_secret()).ok_or_else(invalid)?;
+    if payload.purpose != WORKSPACE_INVITATION || payload.exp <= chrono::Utc::now().timestamp() {
+        return Err(invalid());
+    }
+
+    sqlx::query_as::<_, WorkspaceInvitation>(
+        "SELECT id, workspace_id, email, role, invited_by, expires_at, created_at
+         FROM workspace_invitations
+         WHERE id = $1 AND workspace_id = $2
+           AND accepted_at IS NULL AND declined_at IS NULL AND revoked_at IS NULL
+           AND expires_at > NOW()
+         FOR UPDATE"
+    )
+    .bind(payload.jti)
+    .bind(payload.workspace_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(invalid)
+}
+
+#[rocket::post("/workspaces/<_id>/invitations", data = "<request>")]
+pub async fn create_invitation(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    mailer: &State<Arc<dyn Mailer>>,
+    member: WorkspaceMember,
+    client: ClientInfo,
+    _id: &str,
+    request: Json<CreateInvitationRequest>,
+) -> AppResult<Json<ApiResponse<InvitationResponse>>> {
+    member.user.require_interactive("des invitations")?;
+    member.require_owner()?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    let email = request_data.email.trim().to_lowercase();
+
+    if !WORKSPACE_ROLES.contains(&request_data.role.as_str()) {
+        return Err(AppError::Validation(format!("Rôle inconnu: {}", request_data.role)));
+    }
+
+    let mut tx = db.pool().begin().await?;
+
+    let already_member = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM workspace_members m JOIN users u ON u.id = m.user_id
+             WHERE m.workspace_id = $1 AND u.email = $2
+         )"
+    )
+    .bind(member.workspace_id)
+    .bind(&email)
+    .fetch_one(&mut *tx)
+    .await?;
+    if already_member {
+        return Err(AppError::Conflict(format!("{} est déjà membre du workspace", email)));
+    }
+
+    // Une nouvelle invitation remplace celle en attente pour la même adresse
+    sqlx::query(
+        "UPDATE workspace_invitations SET revoked_at = NOW()
+         WHERE workspace_id = $1 AND email = $2
+           AND accepted_at IS NULL AND declined_at IS NULL AND revoked_at IS NULL"
+    )
+    .bind(member.workspace_id)
+    .bind(&email)
+    .execute(&mut *tx)
+    .await?;
+
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(config.invitation_expiration as i64);
+    let invitation = sqlx::query_as::<_, WorkspaceInvitation>(
+        "INSERT INTO workspace_invitations (id, workspace_id, email, role, invited_by, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id, workspace_id, email, role, invited_by, expires_at, created_at"
+    )
+    .bind(Uuid::new_v4())
+    .bind(member.workspace_id)
+    .bind(&email)
+    .bind(&request_data.role)
+    .bind(member.user.user_id)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let workspace_name = sqlx::query_scalar::<_, String>("SELECT name FROM workspaces WHERE id = $1")
+        .bind(member.workspace_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let token = sign_payload(
+        &InvitationPayload {
+            jti: invitation.id,
+            workspace_id: invitation.workspace_id,
+            purpose: WORKSPACE_INVITATION.to_string(),
+            exp: expires_at.timestamp(),
+        },
+        config.jwt_secret(),
+    )?;
+
+    // L'invitation n'est enregistrée que si l'email a pu être envoyé
+    mailer
+        .send(Email {
+            to: email.clone(),
+            subject: format!("Invitation à rejoindre {}", workspace_name),
+            body: format!(
+                "Vous êtes invité à rejoindre le workspace {} en tant que {}.\n\n\
+                 Pour accepter ou refuser, ouvrez le lien suivant :\n\n{}/invitations?token={}\n",
+                workspace_name, invitation.role, config.app_base_url, token
+            ),
+        })
+        .await?;
+
+    AuditEntry::new("workspace.invitation_create")
+        .by(&member.user)
+        .target("invitation", invitation.id)
+        .after(&serde_json::json!({
+            "workspace_id": invitation.workspace_id,
+            "email": invitation.email,
+            "role": invitation.role,
+        }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        InvitationResponse::from(invitation),
+        "Invitation envoyée".to_string(),
+    )))
+}
+
+#[rocket::get("/workspaces/<_id>/invitations")]
+pub async fn list_invitations(
+    db: &State<Database>,
+    member: WorkspaceMember,
+    _id: &str,
+) -> AppResult<Json<ApiResponse<Vec<InvitationResponse>>>> {
+    member.require_owner()?;
+
+    let invitations = sqlx::query_as::<_, WorkspaceInvitation>(
+        "SELECT id, workspace_id, email, role, invited_by, expires_at, created_at
+         FROM workspace_invitations
+         WHERE workspace_id = $1
+           AND accepted_at IS NULL AND declined_at IS NULL AND revoked_at IS NULL
+           AND expires_at > NOW()
+         ORDER BY created_at DESC"
+    )
+    .bind(member.workspace_id)
+    .fetch_all(db.pool())
+    .await?;
+
+    let responses: Vec<InvitationResponse> = invitations.into_iter().map(InvitationResponse::from).collect();
+    Ok(Json(ApiResponse::success(responses)))
+}
+
+#[rocket::delete("/workspaces/<_id>/invitations/<invitation_id>")]
+pub async fn revoke_invitation(
+    db: &State<Database>,
+    member: WorkspaceMember,
+    client: ClientInfo,
+    _id: &str,
+    invitation_id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    member.user.require_interactive("des invitations")?;
+    member.require_owner()?;
+    let invitation_id = parse_id(invitation_id)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE workspace_invitations SET revoked_at = NOW()
+         WHERE id = $1 AND workspace_id = $2
+           AND accepted_at IS NULL AND declined_at IS NULL AND revoked_at IS NULL"
+    )
+    .bind(invitation_id)
+    .bind(member.workspace_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Invitation avec l'id {} non trouvée", invitation_id)));
+    }
+
+    AuditEntry::new("workspace.invitation_revoke")
+        .by(&member.user)
+        .target("invitation", invitation_id)
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Invitation révoquée".to_string(),
+    )))
+}
+
+#[rocket::post("/invitations/accept", data = "<request>")]
+pub async fn accept_invitation(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<InvitationTokenRequest>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    user.require_interactive("des invitations")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+    let invitation = pending_invitation(&mut *tx, config, &request_data.token).await?;
+
+    // L'invitation est nominative : elle vaut pour le compte de l'adresse invitée
+    let email = sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE id = $1")
+        .bind(user.user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if email != invitation.email {
+        return Err(AppError::Authorization(
+            "Cette invitation est destinée à une autre adresse email".to_string(),
+        ));
+    }
+
+    let joined = sqlx::query(
+        "INSERT INTO workspace_members (workspace_id, user_id, role)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (workspace_id, user_id) DO NOTHING"
+    )
+    .bind(invitation.workspace_id)
+    .bind(user.user_id)
+    .bind(&invitation.role)
+    .execute(&mut *tx)
+    .await?;
+    if joined.rows_affected() == 0 {
+        return Err(AppError::Conflict("Vous êtes déjà membre de ce workspace".to_string()));
+    }
+
+    sqlx::query("UPDATE workspace_invitations SET accepted_at = NOW() WHERE id = $1")
+        .bind(invitation.id)
+        .execute(&mut *tx)
+        .await?;
+
+    AuditEntry::new("workspace.invitation_accept")
+        .by(&user)
+        .target("invitation", invitation.id)
+        .after(&serde_json::json!({
+            "workspace_id": invitation.workspace_id,
+            "user_id": user.user_id,
+            "role": invitation.role,
+        }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Invitation acceptée".to_string(),
+    )))
+}
+
+// Sans authentification : le lien reçu par email suffit pour refuser
+#[rocket::post("/invitations/decline", data = "<request>")]
+pub async fn decline_invitation(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    client: ClientInfo,
+    request: Json<InvitationTokenRequest>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+    let invitation = pending_invitation(&mut *tx, config, &request_data.token).await?;
+
+    sqlx::query("UPDATE workspace_invitations SET declined_at = NOW() WHERE id = $1")
+        .bind(invitation.id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Sans auteur : le refus se fait par le lien, sans compte
+    AuditEntry::new("workspace.invitation_decline")
+        .target("invitation", invitation.id)
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Invitation refusée".to_string(),
+    )))
+}
@@ -1,4 +1,15 @@
 pub mod handlers;
 pub mod dto;
 pub mod task_handlers;
-
+pub mod auth_handlers;
+pub mod admin_handlers;
+pub mod api_key_handlers;
+pub mod account_handlers;
+pub mod two_factor_handlers;
+pub mod session_handlers;
+pub mod workspace_handlers;
+pub mod invitation_handlers;
+pub mod audit_handlers;
+pub mod profile_handlers;
+pub mod task_query;
+pub mod task_query_language;
@@ -0,0 +1,260 @@
+use std::sync::Arc;
+use rocket::serde::json::Json;
+use rocket::State;
+use uuid::Uuid;
+use crate::api::account_handlers::{delete_account, send_verification_email};
+use crate::api::dto::ApiResponse;
+use crate::api::session_handlers::revoke_sessions;
+use crate::audit::AuditEntry;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::lockout::LoginAttempt;
+use crate::mailer::Mailer;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::client::ClientInfo;
+use crate::models::{
+    ChangePasswordRequest, DeleteAccountRequest, UpdateProfileRequest, User, UserResponse,
+};
+use crate::utils::hash::{hash_password, verify_password};
+use crate::utils::validation::validate;
+
+async fn load_user<'e, E>(executor: E, user_id: Uuid) -> AppResult<User>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, User>(
+        "SELECT id, email, name, password_hash, email_verified_at, created_at, updated_at
+         FROM users
+         WHERE id = $1
+         FOR UPDATE"
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Utilisateur non trouvé".to_string()))
+}
+
+/// Confirme une opération sensible par le mot de passe actuel
+///
+/// Les erreurs comptent dans les échecs de connexion du compte, pour qu'un
+/// token volé ne permette pas de deviner le mot de passe.
+async fn confirm_password(
+    db: &Database,
+    config: &AppConfig,
+    client: &ClientInfo,
+    user: &User,
+    password: Option<&str>,
+) -> AppResult<()> {
+    let password_hash = match user.password_hash.as_deref() {
+        Some(password_hash) => password_hash,
+        // Compte externe (OIDC) : pas de mot de passe local à vérifier
+        None => return Ok(()),
+    };
+    let password = password
+        .filter(|password| !password.is_empty())
+        .ok_or_else(|| AppError::Validation("Mot de passe actuel requis".to_string()))?;
+
+    let attempt = LoginAttempt::new(&user.email, client.ip);
+    attempt.ensure_allowed(db).await?;
+    if !verify_password(password, password_hash)? {
+        attempt.record_failure(db, config).await?;
+        return Err(AppError::Authorization("Mot de passe actuel incorrect".to_string()));
+    }
+    Ok(())
+}
+
+#[rocket::get("/me")]
+pub async fn get_profile(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<ApiResponse<UserResponse>>> {
+    let profile = sqlx::query_as::<_, User>(
+        "SELECT id, email, name, password_hash, email_verified_at, created_at, updated_at
+         FROM users
+         WHERE id = $1"
+    )
+    .bind(user.user_id)
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Utilisateur non trouvé".to_string()))?;
+
+    Ok(Json(ApiResponse::success(UserResponse::from(profile))))
+}
+
+/// Modifie le nom et/ou l'email ; une nouvelle adresse doit être vérifiée
+#[rocket::put("/me", data = "<request>")]
+pub async fn update_profile(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    mailer: &State<Arc<dyn Mailer>>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<UpdateProfileRequest>,
+) -> AppResult<Json<ApiResponse<UserResponse>>> {
+    user.require_interactive("du compte")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+    let current = load_user(&mut *tx, user.user_id).await?;
+
+    let new_email = request_data
+        .email
+        .map(|email| email.trim().to_lowercase())
+        .filter(|email| *email != current.email);
+
+    if let Some(email) = &new_email {
+        if current.password_hash.is_none() {
+            return Err(AppError::BadRequest(
+                "L'adresse d'un compte externe est gérée par son fournisseur".to_string(),
+            ));
+        }
+        confirm_password(db, config, &client, &current, request_data.current_password.as_deref()).await?;
+
+        let taken = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)")
+            .bind(email)
+            .fetch_one(&mut *tx)
+            .await?;
+        if taken {
+            return Err(AppError::Conflict(format!("Un compte existe déjà pour l'email {}", email)));
+        }
+    }
+
+    // Une nouvelle adresse repasse à l'état non vérifié
+    let updated = sqlx::query_as::<_, User>(
+        "UPDATE users SET
+             name = COALESCE($1, name),
+             email = COALESCE($2, email),
+             email_verified_at = CASE WHEN $2 IS NULL THEN email_verified_at ELSE NULL END,
+             updated_at = NOW()
+         WHERE id = $3
+         RETURNING id, email, name, password_hash, email_verified_at, created_at, updated_at"
+    )
+    .bind(&request_data.name)
+    .bind(&new_email)
+    .bind(user.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            AppError::Conflict("Un compte existe déjà pour cet email".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    AuditEntry::new("account.update")
+        .by(&user)
+        .target("user", user.user_id)
+        .before(&UserResponse::from(current))?
+        .after(&UserResponse::from(updated.clone()))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    // Comme à l'inscription, l'email de vérification peut être redemandé en cas d'échec
+    if let Some(email) = &new_email {
+        if let Err(e) = send_verification_email(db, config, mailer.as_ref(), user.user_id, email).await {
+            tracing::error!(user_id = %user.user_id, "Erreur lors de l'envoi de l'email de vérification: {}", e);
+        }
+    }
+
+    let message = if new_email.is_some() {
+        "Profil mis à jour, confirmez votre nouvelle adresse email"
+    } else {
+        "Profil mis à jour"
+    };
+    Ok(Json(ApiResponse::success_with_message(
+        UserResponse::from(updated),
+        message.to_string(),
+    )))
+}
+
+/// Change le mot de passe et déconnecte les autres sessions
+#[rocket::put("/me/password", data = "<request>")]
+pub async fn change_password(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<ChangePasswordRequest>,
+) -> AppResult<Json<ApiResponse<u64>>> {
+    user.require_interactive("du compte")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+    let current = load_user(&mut *tx, user.user_id).await?;
+
+    if current.password_hash.is_none() {
+        return Err(AppError::BadRequest(
+            "Le mot de passe d'un compte externe est géré par son fournisseur".to_string(),
+        ));
+    }
+    confirm_password(db, config, &client, &current, Some(&request_data.current_password)).await?;
+
+    let password_hash = hash_password(&request_data.new_password, config)?;
+    sqlx::query("UPDATE users SET password_hash = $1, updated_at = NOW() WHERE id = $2")
+        .bind(&password_hash)
+        .bind(user.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // La session courante reste ouverte
+    let revoked = revoke_sessions(&mut tx, user.user_id, None, user.claims.sid).await?;
+
+    AuditEntry::new("account.password_change")
+        .by(&user)
+        .target("user", user.user_id)
+        .after(&serde_json::json!({ "revoked_sessions": revoked }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        revoked,
+        "Mot de passe modifié, autres sessions déconnectées".to_string(),
+    )))
+}
+
+/// Supprime son propre compte et ses données (voir `delete_account`)
+#[rocket::delete("/me", data = "<request>")]
+pub async fn delete_profile(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Option<Json<DeleteAccountRequest>>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    user.require_interactive("du compte")?;
+    let password = match request {
+        Some(request) => {
+            let request_data = request.into_inner();
+            validate(&request_data)?;
+            request_data.password
+        }
+        None => None,
+    };
+
+    let mut tx = db.pool().begin().await?;
+    let current = load_user(&mut *tx, user.user_id).await?;
+    confirm_password(db, config, &client, &current, password.as_deref()).await?;
+
+    delete_account(&mut tx, user.user_id, &user, &client).await?;
+
+    AuditEntry::new("account.delete")
+        .by(&user)
+        .target("user", user.user_id)
+        .before(&serde_json::json!({ "email": current.email, "name": current.name }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Compte supprimé".to_string(),
+    )))
+}
@@ -0,0 +1,194 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use uuid::Uuid;
+use crate::api::dto::ApiResponse;
+use crate::audit::AuditEntry;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::client::ClientInfo;
+use crate::models::{Session, SessionResponse};
+use crate::utils::validation::parse_id;
+
+/// Ouvre une session pour une nouvelle connexion et retourne son identifiant
+///
+/// L'identifiant sert aussi de famille aux refresh tokens de la connexion.
+pub async fn start_session<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    client: &ClientInfo,
+    expiration: u64,
+) -> AppResult<Uuid>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let session_id = Uuid::new_v4();
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expiration as i64);
+
+    sqlx::query(
+        "INSERT INTO sessions (id, user_id, user_agent, ip_address, expires_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(&client.user_agent)
+    .bind(client.ip.map(|ip| ip.to_string()))
+    .bind(expires_at)
+    .execute(executor)
+    .await?;
+
+    Ok(session_id)
+}
+
+/// Prolonge une session lors d'un refresh ; retourne `false` si elle est révoquée
+///
+/// Les familles de refresh tokens antérieures aux sessions en reçoivent une.
+pub async fn resume_session<'e, E>(
+    executor: E,
+    session_id: Uuid,
+    user_id: Uuid,
+    client: &ClientInfo,
+    expiration: u64,
+) -> AppResult<bool>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expiration as i64);
+
+    let resumed = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO sessions (id, user_id, user_agent, ip_address, expires_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (id) DO UPDATE SET
+             user_agent = EXCLUDED.user_agent,
+             ip_address = EXCLUDED.ip_address,
+             last_seen_at = NOW(),
+             expires_at = EXCLUDED.expires_at
+         WHERE sessions.user_id = EXCLUDED.user_id AND sessions.revoked_at IS NULL
+         RETURNING id"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(&client.user_agent)
+    .bind(client.ip.map(|ip| ip.to_string()))
+    .bind(expires_at)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(resumed.is_some())
+}
+
+/// Révoque des sessions d'un utilisateur et les refresh tokens qui en sont issus
+///
+/// `session_id` désigne une session ; à défaut, toutes les sessions sauf `except`.
+/// Retourne le nombre de sessions révoquées.
+pub async fn revoke_sessions(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    session_id: Option<Uuid>,
+    except: Option<Uuid>,
+) -> AppResult<u64> {
+    let revoked = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE sessions SET revoked_at = NOW()
+         WHERE user_id = $1 AND revoked_at IS NULL
+           AND ($2::UUID IS NULL OR id = $2)
+           AND ($3::UUID IS NULL OR id <> $3)
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .bind(except)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = NOW()
+         WHERE user_id = $1 AND revoked_at IS NULL AND family_id = ANY($2)"
+    )
+    .bind(user_id)
+    .bind(&revoked)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(revoked.len() as u64)
+}
+
+#[rocket::get("/sessions")]
+pub async fn list_sessions(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<ApiResponse<Vec<SessionResponse>>>> {
+    user.require_interactive("des sessions")?;
+
+    let sessions = sqlx::query_as::<_, Session>(
+        "SELECT id, user_agent, ip_address, created_at, last_seen_at, expires_at
+         FROM sessions
+         WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+         ORDER BY last_seen_at DESC"
+    )
+    .bind(user.user_id)
+    .fetch_all(db.pool())
+    .await?;
+
+    let responses: Vec<SessionResponse> = sessions
+        .into_iter()
+        .map(|session| SessionResponse::new(session, user.claims.sid))
+        .collect();
+    Ok(Json(ApiResponse::success(responses)))
+}
+
+#[rocket::delete("/sessions/<id>")]
+pub async fn revoke_session(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    user.require_interactive("des sessions")?;
+    let id = parse_id(id)?;
+
+    let mut tx = db.pool().begin().await?;
+    let revoked = revoke_sessions(&mut tx, user.user_id, Some(id), None).await?;
+
+    if revoked == 0 {
+        return Err(AppError::NotFound(format!("Session avec l'id {} non trouvée", id)));
+    }
+
+    AuditEntry::new("session.revoke")
+        .by(&user)
+        .target("session", id)
+        .record(&mut *tx, &client)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Session révoquée".to_string(),
+    )))
+}
+
+#[rocket::delete("/sessions")]
+pub async fn revoke_other_sessions(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+) -> AppResult<Json<ApiResponse<u64>>> {
+    user.require_interactive("des sessions")?;
+    let current = user.claims.sid.ok_or_else(|| {
+        AppError::BadRequest("Le token courant n'est rattaché à aucune session".to_string())
+    })?;
+
+    let mut tx = db.pool().begin().await?;
+    let revoked = revoke_sessions(&mut tx, user.user_id, None, Some(current)).await?;
+    AuditEntry::new("session.revoke_others")
+        .by(&user)
+        .target("user", user.user_id)
+        .after(&serde_json::json!({ "revoked": revoked }))?
+        .record(&mut *tx, &client)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        revoked,
+        "Autres sessions révoquées".to_string(),
+    )))
+}
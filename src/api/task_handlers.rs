@@ -1,82 +1,299 @@
+use rocket::http::uri::Origin;
 use rocket::serde::json::Json;
-use rocket::State;
+use rocket::{Either, State};
+use sqlx::{Postgres, QueryBuilder};
 use uuid::Uuid;
-use crate::api::dto::{ApiResponse, PaginationParams};
+use crate::api::dto::{ApiResponse, CursorPage, Paginated, PaginationParams};
+use crate::api::task_query::{
+    normalize_tags, validate_language, TaskCursor, TaskFilter, TaskListParams, TaskScope, TaskSearch,
+    TaskSearchParams, DEFAULT_TASK_LANGUAGE,
+};
+use crate::audit::AuditEntry;
+use crate::config::AppConfig;
 use crate::database::Database;
 use crate::errors::{AppError, AppResult};
-use crate::models::{CreateTaskRequest, Task, TaskResponse, UpdateTaskRequest};
-use crate::utils::validation::validate;
+use crate::middleware::client::ClientInfo;
+use crate::middleware::scopes::{RequireScope, TasksRead, TasksWrite};
+use crate::middleware::workspace::{WorkspaceMember, EDITOR, OWNER};
+use crate::models::{
+    CreateTaskRequest, ShareTaskRequest, Task, TaskResponse, TaskSearchHit, TaskSearchResult,
+    TaskShareResponse, UpdateTaskRequest,
+};
+use crate::utils::validation::{parse_id, validate};
 
-#[rocket::get("/tasks?<page>&<limit>")]
-pub async fn get_tasks(
-    db: &State<Database>,
-    page: Option<u64>,
-    limit: Option<u64>,
-) -> AppResult<Json<ApiResponse<Vec<TaskResponse>>>> {
-    let params = PaginationParams { page, limit };
-    let tasks = sqlx::query_as::<_, Task>(
-        "SELECT id, title, description, completed, created_at, updated_at 
+const SHARE_READ: &str = "read";
+const SHARE_WRITE: &str = "write";
+
+const TASK_LIST_SELECT: &str =
+    "SELECT t.id, t.workspace_id, t.owner_id, t.title, t.description, t.completed, t.language, t.tags, t.due_at, t.created_at, t.updated_at";
+
+// Niveau d'accès d'un utilisateur à une tâche
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TaskAccess {
+    Read,
+    Write,
+}
+
+/// Charge une tâche avec l'accès dont dispose l'utilisateur
+///
+/// L'accès vient du rôle dans le workspace actif si la tâche en fait partie,
+/// sinon (ou en plus) d'un partage ; le plus large des deux l'emporte.
+/// Une tâche inaccessible est signalée comme introuvable. Avec `lock`, la
+/// ligne est verrouillée (`FOR UPDATE`) jusqu'à la fin de la transaction :
+/// une modification concurrente attend, et l'état lu reste celui modifié.
+async fn load_task_access(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+    workspace: Option<&WorkspaceMember>,
+    id: Uuid,
+    lock: bool,
+) -> AppResult<(Task, TaskAccess)> {
+    let not_found = || AppError::NotFound(format!("Tâche avec l'id {} non trouvée", id));
+
+    let task = sqlx::query_as::<_, Task>(&format!(
+        "SELECT id, workspace_id, owner_id, title, description, completed, language, tags, due_at, created_at, updated_at 
          FROM tasks 
-         ORDER BY created_at DESC 
-         LIMIT $1 OFFSET $2"
+         WHERE id = $1{}",
+        if lock { " FOR UPDATE" } else { "" }
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(not_found)?;
+
+    let member_access = workspace
+        .filter(|member| task.workspace_id == Some(member.workspace_id))
+        .map(|member| match member.role.as_str() {
+            OWNER | EDITOR => TaskAccess::Write,
+            _ => TaskAccess::Read,
+        });
+
+    let share_access = sqlx::query_scalar::<_, String>(
+        "SELECT permission FROM task_shares WHERE task_id = $1 AND user_id = $2"
     )
-    .bind(params.limit() as i64)
-    .bind(params.offset() as i64)
-    .fetch_all(db.pool())
-    .await?;
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .map(|permission| match permission.as_str() {
+        SHARE_WRITE => TaskAccess::Write,
+        _ => TaskAccess::Read,
+    });
+
+    let access = member_access.max(share_access).ok_or_else(not_found)?;
+    Ok((task, access))
+}
+
+fn require_write(access: TaskAccess) -> AppResult<()> {
+    if access < TaskAccess::Write {
+        return Err(AppError::Authorization(
+            "Accès en lecture seule à cette tâche".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Liste les tâches du workspace actif, ou avec `shared_with_me=true` celles
+/// partagées avec l'utilisateur (le workspace n'est alors pas requis)
+///
+/// Filtres et tri : voir `TaskListParams`. Avec `cursor`, la liste est
+/// parcourue par curseur plutôt que par numéro de page.
+#[rocket::get("/tasks?<params..>")]
+pub async fn get_tasks(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    access: RequireScope<TasksRead>,
+    workspace: Result<WorkspaceMember, AppError>,
+    uri: &Origin<'_>,
+    params: TaskListParams<'_>,
+) -> AppResult<Either<Paginated<TaskResponse>, CursorPage<TaskResponse>>> {
+    let filter = TaskFilter::from_params(&params)?;
+    let pagination = PaginationParams { page: params.page, limit: params.limit };
+    let scope = if params.shared_with_me.unwrap_or(false) {
+        TaskScope::SharedWith(access.user.user_id)
+    } else {
+        TaskScope::Workspace(workspace?.workspace_id)
+    };
+
+    if let Some(cursor) = params.cursor {
+        if params.page.is_some() {
+            return Err(AppError::BadRequest("page et cursor ne peuvent pas être combinés".to_string()));
+        }
+        return get_tasks_after_cursor(db, config, scope, &filter, cursor, pagination.limit(), uri)
+            .await
+            .map(Either::Right);
+    }
+
+    let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*)");
+    scope.push_from(&mut count);
+    filter.push_conditions(&mut count);
+    let total = count.build_query_scalar::<i64>().fetch_one(db.pool()).await?;
+
+    let mut query = QueryBuilder::<Postgres>::new(TASK_LIST_SELECT);
+    scope.push_from(&mut query);
+    filter.push_conditions(&mut query);
+    filter.sort.push_order_by(&mut query);
+    query
+        .push(" LIMIT ")
+        .push_bind(pagination.limit() as i64)
+        .push(" OFFSET ")
+        .push_bind(pagination.offset() as i64);
+
+    let tasks = query.build_query_as::<Task>().fetch_all(db.pool()).await?;
+
+    let responses: Vec<TaskResponse> = tasks.into_iter().map(TaskResponse::from).collect();
+    Ok(Either::Left(Paginated::new(responses, total as u64, &pagination, uri)))
+}
+
+// Page suivant le curseur (vide pour la première), sans comptage du total
+async fn get_tasks_after_cursor(
+    db: &Database,
+    config: &AppConfig,
+    scope: TaskScope,
+    filter: &TaskFilter,
+    cursor: &str,
+    limit: u64,
+    uri: &Origin<'_>,
+) -> AppResult<CursorPage<TaskResponse>> {
+    filter.sort.ensure_keyset()?;
+    let position = match cursor {
+        "" => None,
+        token => Some(TaskCursor::decode(token, &filter.sort, config.jwt_secret())?),
+    };
+
+    let mut query = QueryBuilder::<Postgres>::new(TASK_LIST_SELECT);
+    scope.push_from(&mut query);
+    filter.push_conditions(&mut query);
+    if let Some(position) = position {
+        position.push_condition(&mut query);
+    }
+    filter.sort.push_order_by(&mut query);
+    // Une tâche de plus indique s'il reste une page
+    query.push(" LIMIT ").push_bind(limit as i64 + 1);
+
+    let mut tasks = query.build_query_as::<Task>().fetch_all(db.pool()).await?;
+
+    let next_cursor = if tasks.len() as u64 > limit {
+        tasks.truncate(limit as usize);
+        tasks
+            .last()
+            .map(|task| TaskCursor::after(task, &filter.sort).encode(config.jwt_secret()))
+            .transpose()?
+    } else {
+        None
+    };
 
     let responses: Vec<TaskResponse> = tasks.into_iter().map(TaskResponse::from).collect();
-    Ok(Json(ApiResponse::success(responses)))
+    Ok(CursorPage::new(responses, limit, next_cursor, uri))
+}
+
+/// Recherche plein texte dans le titre et la description des tâches
+///
+/// `q` accepte des mots, des préfixes (`cour*`) et des phrases entre
+/// guillemets ; tous doivent être trouvés. Même périmètre que `GET /tasks`.
+#[rocket::get("/tasks/search?<params..>")]
+pub async fn search_tasks(
+    db: &State<Database>,
+    access: RequireScope<TasksRead>,
+    workspace: Result<WorkspaceMember, AppError>,
+    uri: &Origin<'_>,
+    params: TaskSearchParams<'_>,
+) -> AppResult<Paginated<TaskSearchResult>> {
+    let search = TaskSearch::from_params(&params)?;
+    let pagination = PaginationParams { page: params.page, limit: params.limit };
+    let scope = if params.shared_with_me.unwrap_or(false) {
+        TaskScope::SharedWith(access.user.user_id)
+    } else {
+        TaskScope::Workspace(workspace?.workspace_id)
+    };
+
+    let mut count = QueryBuilder::<Postgres>::new("");
+    search.push_with(&mut count);
+    count.push("SELECT COUNT(*)");
+    scope.push_from(&mut count);
+    search.push_conditions(&mut count);
+    let total = count.build_query_scalar::<i64>().fetch_one(db.pool()).await?;
+
+    let mut query = QueryBuilder::<Postgres>::new("");
+    search.push_with(&mut query);
+    query.push(TASK_LIST_SELECT);
+    search.push_columns(&mut query);
+    scope.push_from(&mut query);
+    search.push_conditions(&mut query);
+    search.push_order_by(&mut query);
+    query
+        .push(" LIMIT ")
+        .push_bind(pagination.limit() as i64)
+        .push(" OFFSET ")
+        .push_bind(pagination.offset() as i64);
+
+    let hits = query.build_query_as::<TaskSearchHit>().fetch_all(db.pool()).await?;
+
+    let results: Vec<TaskSearchResult> = hits.into_iter().map(TaskSearchResult::from).collect();
+    Ok(Paginated::new(results, total as u64, &pagination, uri))
 }
 
 #[rocket::get("/tasks/<id>")]
 pub async fn get_task(
     db: &State<Database>,
+    access: RequireScope<TasksRead>,
+    workspace: Option<WorkspaceMember>,
     id: &str,
 ) -> AppResult<Json<ApiResponse<TaskResponse>>> {
-    let id_str = id;
-    let id = Uuid::parse_str(id_str)
-        .map_err(|_| AppError::BadRequest(format!("ID invalide: {}", id_str)))?;
-    
-    let task = sqlx::query_as::<_, Task>(
-        "SELECT id, title, description, completed, created_at, updated_at 
-         FROM tasks 
-         WHERE id = $1"
-    )
-    .bind(id)
-    .fetch_optional(db.pool())
-    .await?;
+    let id = parse_id(id)?;
+    let mut conn = db.pool().acquire().await?;
+    let (task, _) = load_task_access(&mut conn, access.user.user_id, workspace.as_ref(), id, false).await?;
 
-    match task {
-        Some(task) => Ok(Json(ApiResponse::success(TaskResponse::from(task)))),
-        None => Err(AppError::NotFound(format!("Tâche avec l'id {} non trouvée", id))),
-    }
+    Ok(Json(ApiResponse::success(TaskResponse::from(task))))
 }
 
 #[rocket::post("/tasks", data = "<request>")]
 pub async fn create_task(
     db: &State<Database>,
+    access: RequireScope<TasksWrite>,
+    workspace: WorkspaceMember,
+    client: ClientInfo,
     request: Json<CreateTaskRequest>,
 ) -> AppResult<Json<ApiResponse<TaskResponse>>> {
+    workspace.require_editor()?;
     let request_data = request.into_inner();
     validate(&request_data)?;
+    let language = request_data.language.as_deref().unwrap_or(DEFAULT_TASK_LANGUAGE);
+    validate_language(language)?;
+    let tags = normalize_tags(request_data.tags.as_deref().unwrap_or_default())?;
     let id = Uuid::new_v4();
     let now = chrono::Utc::now();
 
+    let mut tx = db.pool().begin().await?;
+
     let task = sqlx::query_as::<_, Task>(
-        "INSERT INTO tasks (id, title, description, completed, created_at, updated_at)
-         VALUES ($1, $2, $3, $4, $5, $6)
-         RETURNING id, title, description, completed, created_at, updated_at"
+        "INSERT INTO tasks (id, workspace_id, owner_id, title, description, completed, language, tags, due_at, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         RETURNING id, workspace_id, owner_id, title, description, completed, language, tags, due_at, created_at, updated_at"
     )
     .bind(id)
+    .bind(workspace.workspace_id)
+    .bind(access.user.user_id)
     .bind(&request_data.title)
     .bind(&request_data.description)
     .bind(false)
+    .bind(language)
+    .bind(&tags)
+    .bind(request_data.due_at)
     .bind(now)
     .bind(now)
-    .fetch_one(db.pool())
+    .fetch_one(&mut *tx)
     .await?;
 
+    AuditEntry::new("task.create")
+        .by(&access.user)
+        .target("task", task.id)
+        .after(&task)?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
     Ok(Json(ApiResponse::success_with_message(
         TaskResponse::from(task),
         "Tâche créée avec succès".to_string(),
@@ -86,29 +303,27 @@ pub async fn create_task(
 #[rocket::put("/tasks/<id>", data = "<request>")]
 pub async fn update_task(
     db: &State<Database>,
+    access: RequireScope<TasksWrite>,
+    workspace: Option<WorkspaceMember>,
+    client: ClientInfo,
     id: &str,
     request: Json<UpdateTaskRequest>,
 ) -> AppResult<Json<ApiResponse<TaskResponse>>> {
-    let id_str = id;
-    let id = Uuid::parse_str(id_str)
-        .map_err(|_| AppError::BadRequest(format!("ID invalide: {}", id_str)))?;
+    let id = parse_id(id)?;
     
     let request_data = request.into_inner();
     validate(&request_data)?;
-    // Récupérer la tâche existante
-    let existing_task = sqlx::query_as::<_, Task>(
-        "SELECT id, title, description, completed, created_at, updated_at 
-         FROM tasks 
-         WHERE id = $1"
-    )
-    .bind(id)
-    .fetch_optional(db.pool())
-    .await?;
+    if let Some(language) = &request_data.language {
+        validate_language(language)?;
+    }
+    let tags = request_data.tags.as_deref().map(normalize_tags).transpose()?;
 
-    let mut task = match existing_task {
-        Some(t) => t,
-        None => return Err(AppError::NotFound(format!("Tâche avec l'id {} non trouvée", id))),
-    };
+    let mut tx = db.pool().begin().await?;
+
+    // Récupérer la tâche existante, verrouillée jusqu'à la fin de la mise à jour
+    let (mut task, task_access) = load_task_access(&mut tx, access.user.user_id, workspace.as_ref(), id, true).await?;
+    require_write(task_access)?;
+    let before = task.clone();
 
     // Mettre à jour les champs fournis
     if let Some(title) = request_data.title {
@@ -120,22 +335,45 @@ pub async fn update_task(
     if let Some(completed) = request_data.completed {
         task.completed = completed;
     }
+    if let Some(language) = request_data.language {
+        task.language = language;
+    }
+    if let Some(tags) = tags {
+        task.tags = tags;
+    }
+    if request_data.due_at.is_some() {
+        task.due_at = request_data.due_at;
+    }
     task.updated_at = chrono::Utc::now();
 
     // Sauvegarder les modifications
     let updated_task = sqlx::query_as::<_, Task>(
         "UPDATE tasks 
-         SET title = $1, description = $2, completed = $3, updated_at = $4
-         WHERE id = $5
-         RETURNING id, title, description, completed, created_at, updated_at"
+         SET title = $1, description = $2, completed = $3, language = $4, tags = $5, due_at = $6, updated_at = $7
+         WHERE id = $8
+         RETURNING id, workspace_id, owner_id, title, description, completed, language, tags, due_at, created_at, updated_at"
     )
     .bind(&task.title)
     .bind(&task.description)
     .bind(task.completed)
+    .bind(&task.language)
+    .bind(&task.tags)
+    .bind(task.due_at)
     .bind(task.updated_at)
     .bind(id)
-    .fetch_one(db.pool())
-    .await?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Tâche avec l'id {} non trouvée", id)))?;
+
+    AuditEntry::new("task.update")
+        .by(&access.user)
+        .target("task", id)
+        .before(&before)?
+        .after(&updated_task)?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
 
     Ok(Json(ApiResponse::success_with_message(
         TaskResponse::from(updated_task),
@@ -146,20 +384,34 @@ pub async fn update_task(
 #[rocket::delete("/tasks/<id>")]
 pub async fn delete_task(
     db: &State<Database>,
+    access: RequireScope<TasksWrite>,
+    workspace: Option<WorkspaceMember>,
+    client: ClientInfo,
     id: &str,
 ) -> AppResult<Json<ApiResponse<()>>> {
-    let id_str = id;
-    let id = Uuid::parse_str(id_str)
-        .map_err(|_| AppError::BadRequest(format!("ID invalide: {}", id_str)))?;
-    
-    let result = sqlx::query("DELETE FROM tasks WHERE id = $1")
-        .bind(id)
-        .execute(db.pool())
+    let id = parse_id(id)?;
+
+    let mut tx = db.pool().begin().await?;
+    let (_, task_access) = load_task_access(&mut tx, access.user.user_id, workspace.as_ref(), id, true).await?;
+    require_write(task_access)?;
+
+    let deleted = sqlx::query_as::<_, Task>(
+        "DELETE FROM tasks WHERE id = $1
+         RETURNING id, workspace_id, owner_id, title, description, completed, language, tags, due_at, created_at, updated_at"
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Tâche avec l'id {} non trouvée", id)))?;
+
+    AuditEntry::new("task.delete")
+        .by(&access.user)
+        .target("task", id)
+        .before(&deleted)?
+        .record(&mut *tx, &client)
         .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound(format!("Tâche avec l'id {} non trouvée", id)));
-    }
+    tx.commit().await?;
 
     Ok(Json(ApiResponse::success_with_message(
         (),
@@ -167,3 +419,157 @@ pub async fn delete_task(
     )))
 }
 
+// Les partages se gèrent depuis le workspace de la tâche, avec le rôle owner ou editor
+async fn ensure_task_in_workspace(db: &Database, workspace: &WorkspaceMember, id: Uuid) -> AppResult<()> {
+    workspace.require_editor()?;
+
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = $1 AND workspace_id = $2)"
+    )
+    .bind(id)
+    .bind(workspace.workspace_id)
+    .fetch_one(db.pool())
+    .await?;
+
+    if !exists {
+        return Err(AppError::NotFound(format!("Tâche avec l'id {} non trouvée", id)));
+    }
+    Ok(())
+}
+
+#[rocket::get("/tasks/<id>/shares")]
+pub async fn list_task_shares(
+    db: &State<Database>,
+    _access: RequireScope<TasksRead>,
+    workspace: WorkspaceMember,
+    id: &str,
+) -> AppResult<Json<ApiResponse<Vec<TaskShareResponse>>>> {
+    let id = parse_id(id)?;
+    ensure_task_in_workspace(db, &workspace, id).await?;
+
+    let shares = sqlx::query_as::<_, TaskShareResponse>(
+        "SELECT u.id AS user_id, u.email, u.name, s.permission, s.granted_by, s.created_at
+         FROM task_shares s
+         JOIN users u ON u.id = s.user_id
+         WHERE s.task_id = $1
+         ORDER BY s.created_at"
+    )
+    .bind(id)
+    .fetch_all(db.pool())
+    .await?;
+
+    Ok(Json(ApiResponse::success(shares)))
+}
+
+/// Partage une tâche avec un utilisateur, ou modifie la permission existante
+#[rocket::put("/tasks/<id>/shares", data = "<request>")]
+pub async fn share_task(
+    db: &State<Database>,
+    access: RequireScope<TasksWrite>,
+    workspace: WorkspaceMember,
+    client: ClientInfo,
+    id: &str,
+    request: Json<ShareTaskRequest>,
+) -> AppResult<Json<ApiResponse<TaskShareResponse>>> {
+    let id = parse_id(id)?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    if request_data.permission != SHARE_READ && request_data.permission != SHARE_WRITE {
+        return Err(AppError::Validation(format!("Permission inconnue: {}", request_data.permission)));
+    }
+    ensure_task_in_workspace(db, &workspace, id).await?;
+
+    let email = request_data.email.trim().to_lowercase();
+    let user_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_optional(db.pool())
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Utilisateur {} non trouvé", email)))?;
+    if user_id == access.user.user_id {
+        return Err(AppError::BadRequest("Impossible de partager une tâche avec soi-même".to_string()));
+    }
+
+    let mut tx = db.pool().begin().await?;
+
+    let previous = sqlx::query_scalar::<_, String>(
+        "SELECT permission FROM task_shares WHERE task_id = $1 AND user_id = $2 FOR UPDATE"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let share = sqlx::query_as::<_, TaskShareResponse>(
+        "WITH share AS (
+             INSERT INTO task_shares (task_id, user_id, permission, granted_by)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (task_id, user_id) DO UPDATE SET
+                 permission = EXCLUDED.permission,
+                 granted_by = EXCLUDED.granted_by
+             RETURNING user_id, permission, granted_by, created_at
+         )
+         SELECT u.id AS user_id, u.email, u.name, share.permission, share.granted_by, share.created_at
+         FROM share JOIN users u ON u.id = share.user_id"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(&request_data.permission)
+    .bind(access.user.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut entry = AuditEntry::new("task.share").by(&access.user).target("task", id);
+    if let Some(permission) = previous {
+        entry = entry.before(&serde_json::json!({ "user_id": user_id, "permission": permission }))?;
+    }
+    entry
+        .after(&serde_json::json!({ "user_id": user_id, "permission": share.permission }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        share,
+        "Tâche partagée avec succès".to_string(),
+    )))
+}
+
+#[rocket::delete("/tasks/<id>/shares/<user_id>")]
+pub async fn unshare_task(
+    db: &State<Database>,
+    access: RequireScope<TasksWrite>,
+    workspace: WorkspaceMember,
+    client: ClientInfo,
+    id: &str,
+    user_id: &str,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let id = parse_id(id)?;
+    let user_id = parse_id(user_id)?;
+    ensure_task_in_workspace(db, &workspace, id).await?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let permission = sqlx::query_scalar::<_, String>(
+        "DELETE FROM task_shares WHERE task_id = $1 AND user_id = $2 RETURNING permission"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Aucun partage pour l'utilisateur {}", user_id)))?;
+
+    AuditEntry::new("task.unshare")
+        .by(&access.user)
+        .target("task", id)
+        .before(&serde_json::json!({ "user_id": user_id, "permission": permission }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Partage supprimé".to_string(),
+    )))
+}
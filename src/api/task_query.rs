@@ -0,0 +1,596 @@
+//! Filtres et tri de la liste des tâches (`GET /tasks`)
+//!
+//! Les valeurs saisies ne sont jamais concaténées au SQL : elles sont liées
+//! comme paramètres, et le tri n'accepte que des colonnes connues.
+//!
+//! La liste se parcourt par numéro de page (`page`) ou par curseur (`cursor`).
+//! Le curseur désigne la dernière tâche vue par `(created_at, id)` : la page
+//! suivante reprend juste après elle, sans `OFFSET` et sans doublon ni oubli
+//! lorsque des tâches sont ajoutées entre deux requêtes.
+
+use chrono::{DateTime, Utc};
+use rocket::FromForm;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+use crate::api::task_query_language::TaskQuery;
+use crate::errors::{AppError, AppResult};
+use crate::models::Task;
+use crate::utils::query::{contains_pattern, parse_timestamp};
+use crate::utils::token::{sign_payload, verify_payload};
+
+const TASK_CURSOR: &str = "task_cursor";
+
+/// Paramètres de requête de `GET /tasks`
+#[derive(Debug, FromForm)]
+pub struct TaskListParams<'r> {
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+    pub shared_with_me: Option<bool>,
+    pub completed: Option<bool>,
+    pub created_after: Option<&'r str>,
+    pub created_before: Option<&'r str>,
+    pub updated_after: Option<&'r str>,
+    pub updated_before: Option<&'r str>,
+    pub title: Option<&'r str>,
+    // `champ:asc` ou `champ:desc`
+    pub sort: Option<&'r str>,
+    // Vide pour la première page, puis `next_cursor` de la page précédente
+    pub cursor: Option<&'r str>,
+    // Requête du langage de filtre (voir `task_query_language`)
+    pub q: Option<&'r str>,
+}
+
+/// Tâches parcourues : celles du workspace actif ou celles partagées avec l'utilisateur
+#[derive(Debug, Clone, Copy)]
+pub enum TaskScope {
+    Workspace(Uuid),
+    SharedWith(Uuid),
+}
+
+impl TaskScope {
+    /// Ajoute `FROM tasks t ... WHERE` ; les conditions suivantes commencent par `AND`
+    pub fn push_from(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        match *self {
+            TaskScope::Workspace(workspace_id) => {
+                query.push(" FROM tasks t WHERE t.workspace_id = ").push_bind(workspace_id);
+            }
+            TaskScope::SharedWith(user_id) => {
+                query
+                    .push(" FROM tasks t JOIN task_shares s ON s.task_id = t.id WHERE s.user_id = ")
+                    .push_bind(user_id);
+            }
+        }
+    }
+}
+
+const CREATED_AT: &str = "t.created_at";
+
+/// Colonnes autorisées pour le tri
+const SORT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", CREATED_AT),
+    ("updated_at", "t.updated_at"),
+    ("title", "t.title"),
+    ("completed", "t.completed"),
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaskSort {
+    column: &'static str,
+    descending: bool,
+}
+
+impl Default for TaskSort {
+    fn default() -> Self {
+        Self {
+            column: CREATED_AT,
+            descending: true,
+        }
+    }
+}
+
+impl TaskSort {
+    pub fn parse(value: &str) -> AppResult<Self> {
+        let (field, direction) = value.split_once(':').unwrap_or((value, "asc"));
+
+        let column = SORT_FIELDS
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| {
+                let fields: Vec<&str> = SORT_FIELDS.iter().map(|(name, _)| *name).collect();
+                AppError::BadRequest(format!("Tri inconnu: {} (valeurs: {})", field, fields.join(", ")))
+            })?;
+        let descending = match direction {
+            "asc" => false,
+            "desc" => true,
+            other => return Err(AppError::BadRequest(format!("Ordre de tri inconnu: {} (asc ou desc)", other))),
+        };
+
+        Ok(Self { column, descending })
+    }
+
+    /// Le parcours par curseur suit l'ordre de création, croissant ou décroissant
+    pub fn ensure_keyset(&self) -> AppResult<()> {
+        if self.column != CREATED_AT {
+            return Err(AppError::BadRequest(
+                "La pagination par curseur n'accepte que le tri created_at".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Ajoute `ORDER BY`, départagé par l'identifiant pour un ordre stable
+    pub fn push_order_by(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        query
+            .push(" ORDER BY ")
+            .push(self.column)
+            .push(" ")
+            .push(direction)
+            .push(", t.id ")
+            .push(direction);
+    }
+}
+
+/// Filtres validés de la liste des tâches
+#[derive(Debug, Default)]
+pub struct TaskFilter {
+    pub completed: Option<bool>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    pub title_pattern: Option<String>,
+    pub query: TaskQuery,
+    pub sort: TaskSort,
+}
+
+impl TaskFilter {
+    pub fn from_params(params: &TaskListParams<'_>) -> AppResult<Self> {
+        Ok(Self {
+            completed: params.completed,
+            created_after: parse_timestamp("created_after", params.created_after)?,
+            created_before: parse_timestamp("created_before", params.created_before)?,
+            updated_after: parse_timestamp("updated_after", params.updated_after)?,
+            updated_before: parse_timestamp("updated_before", params.updated_before)?,
+            title_pattern: contains_pattern(params.title),
+            query: TaskQuery::parse(params.q.unwrap_or_default())?,
+            sort: params.sort.map(TaskSort::parse).transpose()?.unwrap_or_default(),
+        })
+    }
+
+    /// Ajoute les conditions à une requête dont la clause `WHERE` est déjà ouverte
+    ///
+    /// Les bornes `*_after` sont inclusives, les bornes `*_before` exclusives.
+    pub fn push_conditions(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        if let Some(completed) = self.completed {
+            query.push(" AND t.completed = ").push_bind(completed);
+        }
+        if let Some(date) = self.created_after {
+            query.push(" AND t.created_at >= ").push_bind(date);
+        }
+        if let Some(date) = self.created_before {
+            query.push(" AND t.created_at < ").push_bind(date);
+        }
+        if let Some(date) = self.updated_after {
+            query.push(" AND t.updated_at >= ").push_bind(date);
+        }
+        if let Some(date) = self.updated_before {
+            query.push(" AND t.updated_at < ").push_bind(date);
+        }
+        if let Some(pattern) = &self.title_pattern {
+            query.push(" AND t.title ILIKE ").push_bind(pattern.clone());
+        }
+        self.query.push_conditions(query);
+    }
+}
+
+// Contenu signé du curseur
+#[derive(Serialize, Deserialize)]
+struct CursorPayload {
+    purpose: String,
+    created_at: DateTime<Utc>,
+    id: Uuid,
+    descending: bool,
+}
+
+/// Position dans la liste : la dernière tâche de la page précédente
+#[derive(Debug, Clone, Copy)]
+pub struct TaskCursor {
+    created_at: DateTime<Utc>,
+    id: Uuid,
+    descending: bool,
+}
+
+impl TaskCursor {
+    pub fn after(task: &Task, sort: &TaskSort) -> Self {
+        Self {
+            created_at: task.created_at,
+            id: task.id,
+            descending: sort.descending,
+        }
+    }
+
+    /// Curseur opaque remis au client ; la signature empêche de le forger
+    pub fn encode(&self, secret: &str) -> AppResult<String> {
+        Ok(sign_payload(
+            &CursorPayload {
+                purpose: TASK_CURSOR.to_string(),
+                created_at: self.created_at,
+                id: self.id,
+                descending: self.descending,
+            },
+            secret,
+        )?)
+    }
+
+    /// Vérifie un curseur reçu ; il doit avoir été émis pour le même ordre de tri
+    pub fn decode(token: &str, sort: &TaskSort, secret: &str) -> AppResult<Self> {
+        let payload = verify_payload::<CursorPayload>(token, secret)
+            .filter(|payload| payload.purpose == TASK_CURSOR)
+            .ok_or_else(|| AppError::BadRequest("Curseur invalide".to_string()))?;
+        if payload.descending != sort.descending {
+            return Err(AppError::BadRequest(
+                "Le curseur a été émis pour un autre ordre de tri".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            created_at: payload.created_at,
+            id: payload.id,
+            descending: payload.descending,
+        })
+    }
+
+    /// Ajoute la condition « après le curseur » dans l'ordre de tri
+    pub fn push_condition(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        let operator = if self.descending { " < " } else { " > " };
+        query
+            .push(" AND (t.created_at, t.id)")
+            .push(operator)
+            .push("(")
+            .push_bind(self.created_at)
+            .push(", ")
+            .push_bind(self.id)
+            .push(")");
+    }
+}
+
+/// Langues des tâches et configurations de recherche Postgres correspondantes
+pub const TASK_LANGUAGES: &[(&str, &str)] = &[("fr", "french"), ("en", "english")];
+
+pub const DEFAULT_TASK_LANGUAGE: &str = "fr";
+
+pub fn validate_language(language: &str) -> AppResult<()> {
+    if !TASK_LANGUAGES.iter().any(|(code, _)| *code == language) {
+        let codes: Vec<&str> = TASK_LANGUAGES.iter().map(|(code, _)| *code).collect();
+        return Err(AppError::Validation(format!(
+            "Langue inconnue: {} (valeurs: {})",
+            language,
+            codes.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 50;
+
+/// Normalise les étiquettes d'une tâche : minuscules, sans doublon
+///
+/// Une étiquette est faite de lettres, chiffres, `-` et `_`, ce qui permet de
+/// l'écrire telle quelle dans `tag:` du langage de filtre.
+pub fn normalize_tags(tags: &[String]) -> AppResult<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Étiquette invalide: \"{}\" (1 à {} caractères)",
+                tag, MAX_TAG_LENGTH
+            )));
+        }
+        if !tag.chars().all(is_tag_char) {
+            return Err(AppError::Validation(format!(
+                "Étiquette invalide: {} (lettres, chiffres, - et _)",
+                tag
+            )));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(AppError::Validation(format!("Au plus {} étiquettes par tâche", MAX_TAGS)));
+    }
+    Ok(normalized)
+}
+
+pub fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Paramètres de requête de `GET /tasks/search`
+#[derive(Debug, FromForm)]
+pub struct TaskSearchParams<'r> {
+    pub q: Option<&'r str>,
+    // `fr` ou `en` ; par défaut, les tâches des deux langues
+    pub lang: Option<&'r str>,
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+    pub shared_with_me: Option<bool>,
+    pub completed: Option<bool>,
+}
+
+const MAX_SEARCH_LENGTH: usize = 200;
+
+// Options de `ts_headline` : titre entier, ou extraits de la description
+const TITLE_HEADLINE: &str = "HighlightAll=true, StartSel=<mark>, StopSel=</mark>";
+const SNIPPET_HEADLINE: &str = "StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MinWords=5, MaxWords=20";
+
+#[derive(Debug, PartialEq)]
+enum SearchTerm {
+    Word(String),
+    // `mot*` : mots commençant par le préfixe
+    Prefix(String),
+    // `"plusieurs mots"` : mots consécutifs
+    Phrase(String),
+}
+
+// Découpe la saisie en termes ; une phrase non refermée court jusqu'à la fin
+fn parse_terms(input: &str) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        rest = &rest[start..];
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let (phrase, after) = quoted.split_once('"').unwrap_or((quoted, ""));
+            if !phrase.trim().is_empty() {
+                terms.push(SearchTerm::Phrase(phrase.trim().to_string()));
+            }
+            rest = after;
+            continue;
+        }
+
+        let end = rest.find(|c: char| c.is_whitespace() || c == '"').unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = &rest[end..];
+        match word.strip_suffix('*') {
+            Some(prefix) => {
+                // Seuls les caractères alphanumériques passent dans la syntaxe de `to_tsquery`
+                let prefix: String = prefix.chars().filter(|c| c.is_alphanumeric()).collect();
+                if !prefix.is_empty() {
+                    terms.push(SearchTerm::Prefix(prefix));
+                }
+            }
+            None => terms.push(SearchTerm::Word(word.to_string())),
+        }
+    }
+
+    terms
+}
+
+/// Recherche plein texte validée : termes, langues et filtres
+///
+/// Tous les termes doivent être trouvés. Chaque tâche est comparée à la requête
+/// analysée dans sa propre langue, pour que la racinisation corresponde à l'index.
+#[derive(Debug)]
+pub struct TaskSearch {
+    terms: Vec<SearchTerm>,
+    languages: Vec<(&'static str, &'static str)>,
+    completed: Option<bool>,
+}
+
+impl TaskSearch {
+    pub fn from_params(params: &TaskSearchParams<'_>) -> AppResult<Self> {
+        let input = params.q.unwrap_or_default();
+        if input.chars().count() > MAX_SEARCH_LENGTH {
+            return Err(AppError::Validation(format!(
+                "La recherche est limitée à {} caractères",
+                MAX_SEARCH_LENGTH
+            )));
+        }
+        let terms = parse_terms(input);
+        if terms.is_empty() {
+            return Err(AppError::BadRequest("Le paramètre q est requis".to_string()));
+        }
+
+        let languages = match params.lang {
+            Some(lang) => {
+                validate_language(lang)?;
+                TASK_LANGUAGES.iter().filter(|(code, _)| *code == lang).copied().collect()
+            }
+            None => TASK_LANGUAGES.to_vec(),
+        };
+
+        Ok(Self {
+            terms,
+            languages,
+            completed: params.completed,
+        })
+    }
+
+    /// Ajoute `WITH search_query AS (...)`, à placer avant le `SELECT`
+    ///
+    /// La CTE calcule une fois la requête de chaque langue, dans une colonne
+    /// nommée par son code.
+    pub fn push_with(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        query.push("WITH search_query AS (SELECT ");
+        for (i, (code, config)) in self.languages.iter().enumerate() {
+            if i > 0 {
+                query.push(", ");
+            }
+            self.push_tsquery(query, config);
+            query.push(" AS ").push(*code);
+        }
+        query.push(") ");
+    }
+
+    // Conjonction des termes analysés avec la configuration `config`
+    fn push_tsquery(&self, query: &mut QueryBuilder<'_, Postgres>, config: &str) {
+        query.push("(");
+        for (i, term) in self.terms.iter().enumerate() {
+            if i > 0 {
+                query.push(" && ");
+            }
+            let (function, value) = match term {
+                SearchTerm::Word(word) => ("plainto_tsquery", word.clone()),
+                SearchTerm::Prefix(prefix) => ("to_tsquery", format!("{}:*", prefix)),
+                SearchTerm::Phrase(phrase) => ("phraseto_tsquery", phrase.clone()),
+            };
+            query
+                .push(function)
+                .push("('")
+                .push(config)
+                .push("', ")
+                .push_bind(value)
+                .push(")");
+        }
+        query.push(")");
+    }
+
+    // `CASE t.language WHEN ... END`, `expression` étant écrite pour chaque langue
+    fn push_per_language(
+        &self,
+        query: &mut QueryBuilder<'_, Postgres>,
+        expression: impl Fn(&mut QueryBuilder<'_, Postgres>, &str, &str),
+    ) {
+        query.push("CASE t.language");
+        for (code, config) in &self.languages {
+            query.push(" WHEN '").push(*code).push("' THEN ");
+            expression(query, code, config);
+        }
+        query.push(" END");
+    }
+
+    /// Ajoute le rang et les extraits surlignés aux colonnes sélectionnées
+    ///
+    /// Le texte est échappé pour HTML avant le surlignage : seules les balises
+    /// `<mark>` ajoutées sont interprétables.
+    pub fn push_columns(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        query.push(", ");
+        self.push_per_language(query, |query, code, _| {
+            query
+                .push("ts_rank(t.search_vector, (SELECT ")
+                .push(code)
+                .push(" FROM search_query))");
+        });
+        query.push(" AS rank, ");
+        self.push_per_language(query, |query, code, config| {
+            push_headline(query, code, config, "t.title", TITLE_HEADLINE);
+        });
+        query.push(" AS title_highlight, ");
+        self.push_per_language(query, |query, code, config| {
+            push_headline(query, code, config, "t.description", SNIPPET_HEADLINE);
+        });
+        query.push(" AS snippet");
+    }
+
+    /// Ajoute les conditions à une requête dont la clause `WHERE` est déjà ouverte
+    pub fn push_conditions(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        if let Some(completed) = self.completed {
+            query.push(" AND t.completed = ").push_bind(completed);
+        }
+        // Une condition par langue, pour que l'index GIN reste utilisable
+        query.push(" AND (");
+        for (i, (code, _)) in self.languages.iter().enumerate() {
+            if i > 0 {
+                query.push(" OR ");
+            }
+            query
+                .push("(t.language = '")
+                .push(*code)
+                .push("' AND t.search_vector @@ (SELECT ")
+                .push(*code)
+                .push(" FROM search_query))");
+        }
+        query.push(")");
+    }
+
+    /// Ajoute `ORDER BY` : les plus pertinentes d'abord, puis les plus récentes
+    pub fn push_order_by(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        query.push(" ORDER BY rank DESC, t.created_at DESC, t.id DESC");
+    }
+}
+
+fn push_headline(query: &mut QueryBuilder<'_, Postgres>, code: &str, config: &str, column: &str, options: &str) {
+    query
+        .push("ts_headline('")
+        .push(config)
+        .push("', replace(replace(replace(")
+        .push(column)
+        .push(", '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), (SELECT ")
+        .push(code)
+        .push(" FROM search_query), '")
+        .push(options)
+        .push("')");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "secret-de-test";
+
+    fn cursor(descending: bool) -> TaskCursor {
+        TaskCursor {
+            created_at: Utc::now(),
+            id: Uuid::new_v4(),
+            descending,
+        }
+    }
+
+    fn sort(descending: bool) -> TaskSort {
+        TaskSort {
+            column: CREATED_AT,
+            descending,
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let original = cursor(true);
+        let token = original.encode(SECRET).unwrap();
+        let decoded = TaskCursor::decode(&token, &sort(true), SECRET).unwrap();
+        assert_eq!((decoded.created_at, decoded.id), (original.created_at, original.id));
+    }
+
+    #[test]
+    fn tampered_cursor_is_rejected() {
+        let token = cursor(true).encode(SECRET).unwrap();
+        let (payload, signature) = token.split_once('.').unwrap();
+
+        // Payload modifié, signature d'un autre secret, ou absente
+        let mut bytes = payload.as_bytes().to_vec();
+        bytes[0] = if bytes[0] == b'A' { b'B' } else { b'A' };
+        let forged = format!("{}.{}", String::from_utf8(bytes).unwrap(), signature);
+        assert!(TaskCursor::decode(&forged, &sort(true), SECRET).is_err());
+        assert!(TaskCursor::decode(&token, &sort(true), "autre-secret").is_err());
+        assert!(TaskCursor::decode(payload, &sort(true), SECRET).is_err());
+        assert!(TaskCursor::decode("", &sort(true), SECRET).is_err());
+    }
+
+    #[test]
+    fn cursor_for_other_direction_is_rejected() {
+        let token = cursor(false).encode(SECRET).unwrap();
+        assert!(TaskCursor::decode(&token, &sort(false), SECRET).is_ok());
+        assert!(TaskCursor::decode(&token, &sort(true), SECRET).is_err());
+    }
+
+    #[test]
+    fn token_signed_for_other_purpose_is_rejected() {
+        let token = sign_payload(
+            &CursorPayload {
+                purpose: "password_reset".to_string(),
+                created_at: Utc::now(),
+                id: Uuid::new_v4(),
+                descending: true,
+            },
+            SECRET,
+        )
+        .unwrap();
+        assert!(TaskCursor::decode(&token, &sort(true), SECRET).is_err());
+    }
+}
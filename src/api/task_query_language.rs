@@ -0,0 +1,565 @@
+//! Langage de filtre de `GET /tasks?q=`
+//!
+//! Une requête est une suite de termes séparés par des espaces, tous requis :
+//!
+//! ```text
+//! is:open tag:backend due:<2026-11-01 "notes de version" -lait
+//! ```
+//!
+//! - un mot ou une phrase entre guillemets est cherché dans le titre ou la description ;
+//! - `champ:valeur` filtre sur un champ (`is`, `title`, `lang`, `tag`, `created`,
+//!   `updated`, `due`) ; les dates acceptent un comparateur (`<`, `<=`, `>`, `>=`, `=`) ;
+//! - `-` devant un terme l'exclut.
+//!
+//! La requête est d'abord analysée en un arbre typé (`TaskQuery`), puis
+//! compilée en conditions SQL dont toutes les valeurs sont liées.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::{Postgres, QueryBuilder};
+use crate::api::task_query::{is_tag_char, TASK_LANGUAGES};
+use crate::errors::AppError;
+use crate::utils::query::contains_pattern;
+
+const MAX_QUERY_LENGTH: usize = 500;
+
+const FIELDS: &[&str] = &["is", "title", "lang", "tag", "created", "updated", "due"];
+
+/// Erreur d'analyse, située dans la requête (position en caractères, à partir de 1)
+#[derive(Debug, PartialEq)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl From<QueryError> for AppError {
+    fn from(error: QueryError) -> Self {
+        AppError::BadRequest(format!("q invalide, position {}: {}", error.position, error.message))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateField {
+    Created,
+    Updated,
+    Due,
+}
+
+impl DateField {
+    fn column(self) -> &'static str {
+        match self {
+            DateField::Created => "t.created_at",
+            DateField::Updated => "t.updated_at",
+            DateField::Due => "t.due_at",
+        }
+    }
+
+    // Sans échéance, une tâche ne satisfait aucune comparaison : `-due:<...` la garde
+    fn nullable(self) -> bool {
+        self == DateField::Due
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+/// Date saisie : un jour entier (`2024-01-01`) ou un instant (RFC 3339)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateValue {
+    Day(DateTime<Utc>),
+    Instant(DateTime<Utc>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Predicate {
+    // Sous-chaîne du titre ou de la description
+    Text(String),
+    Title(String),
+    Completed(bool),
+    Language(&'static str),
+    // Étiquette normalisée (minuscules)
+    Tag(String),
+    Date {
+        field: DateField,
+        comparison: Comparison,
+        value: DateValue,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Clause {
+    pub negated: bool,
+    pub predicate: Predicate,
+}
+
+/// Requête analysée : conjonction de clauses
+#[derive(Debug, Default, PartialEq)]
+pub struct TaskQuery {
+    pub clauses: Vec<Clause>,
+}
+
+impl TaskQuery {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        if input.chars().count() > MAX_QUERY_LENGTH {
+            return Err(QueryError {
+                position: MAX_QUERY_LENGTH + 1,
+                message: format!("la requête est limitée à {} caractères", MAX_QUERY_LENGTH),
+            });
+        }
+
+        let mut parser = Parser { input, pos: 0 };
+        let mut clauses = Vec::new();
+        while parser.skip_whitespace() {
+            clauses.push(parser.clause()?);
+        }
+        Ok(Self { clauses })
+    }
+
+    /// Ajoute les clauses à une requête dont la clause `WHERE` est déjà ouverte
+    pub fn push_conditions(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        for clause in &self.clauses {
+            query.push(if clause.negated { " AND NOT " } else { " AND " });
+            push_predicate(query, &clause.predicate);
+        }
+    }
+}
+
+fn push_predicate(query: &mut QueryBuilder<'_, Postgres>, predicate: &Predicate) {
+    match predicate {
+        Predicate::Text(pattern) => {
+            // COALESCE : une description absente ne rend pas la négation indéterminée
+            query
+                .push("(t.title ILIKE ")
+                .push_bind(pattern.clone())
+                .push(" OR COALESCE(t.description, '') ILIKE ")
+                .push_bind(pattern.clone())
+                .push(")");
+        }
+        Predicate::Title(pattern) => {
+            query.push("(t.title ILIKE ").push_bind(pattern.clone()).push(")");
+        }
+        Predicate::Completed(completed) => {
+            query.push("(t.completed = ").push_bind(*completed).push(")");
+        }
+        Predicate::Language(code) => {
+            query.push("(t.language = ").push_bind(*code).push(")");
+        }
+        Predicate::Tag(tag) => {
+            query.push("(t.tags @> ").push_bind(vec![tag.clone()]).push(")");
+        }
+        Predicate::Date { field, comparison, value } => push_date(query, *field, *comparison, *value),
+    }
+}
+
+// Un jour couvre l'intervalle [minuit, minuit du lendemain[
+fn push_date(query: &mut QueryBuilder<'_, Postgres>, field: DateField, comparison: Comparison, value: DateValue) {
+    let column = field.column();
+    let (start, end, day) = match value {
+        DateValue::Day(start) => (start, start + Duration::days(1), true),
+        DateValue::Instant(instant) => (instant, instant, false),
+    };
+
+    query.push("(");
+    if field.nullable() {
+        query.push(column).push(" IS NOT NULL AND ");
+    }
+    query.push(column);
+    match (comparison, day) {
+        (Comparison::Lt, _) => query.push(" < ").push_bind(start),
+        (Comparison::Le, true) => query.push(" < ").push_bind(end),
+        (Comparison::Le, false) => query.push(" <= ").push_bind(start),
+        (Comparison::Gt, true) => query.push(" >= ").push_bind(end),
+        (Comparison::Gt, false) => query.push(" > ").push_bind(start),
+        (Comparison::Ge, _) => query.push(" >= ").push_bind(start),
+        (Comparison::Eq, true) => query
+            .push(" >= ")
+            .push_bind(start)
+            .push(" AND ")
+            .push(column)
+            .push(" < ")
+            .push_bind(end),
+        (Comparison::Eq, false) => query.push(" = ").push_bind(start),
+    };
+    query.push(")");
+}
+
+// Analyseur descendant ; `pos` est un offset en octets dans `input`
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Avance jusqu'au prochain terme ; `false` en fin de requête
+    fn skip_whitespace(&mut self) -> bool {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+        !trimmed.is_empty()
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c: char| !predicate(c)).unwrap_or(rest.len());
+        self.pos += end;
+        &rest[..end]
+    }
+
+    fn error(&self, pos: usize, message: impl Into<String>) -> QueryError {
+        QueryError {
+            position: self.input[..pos].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    fn clause(&mut self) -> Result<Clause, QueryError> {
+        let start = self.pos;
+        let negated = self.eat('-');
+        if negated && self.peek().is_none_or(char::is_whitespace) {
+            return Err(self.error(start, "terme attendu après -"));
+        }
+
+        let predicate = if self.peek() == Some('"') {
+            Predicate::Text(self.text()?)
+        } else {
+            let word_start = self.pos;
+            let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+            let is_field = name.starts_with(|c: char| c.is_ascii_alphabetic());
+            if is_field && self.eat(':') {
+                self.qualifier(name, word_start)?
+            } else {
+                self.pos = word_start;
+                Predicate::Text(self.text()?)
+            }
+        };
+
+        Ok(Clause { negated, predicate })
+    }
+
+    // Mot ou phrase, converti en motif de sous-chaîne
+    fn text(&mut self) -> Result<String, QueryError> {
+        let start = self.pos;
+        let value = self.value()?;
+        contains_pattern(Some(value)).ok_or_else(|| self.error(start, "texte vide"))
+    }
+
+    // Phrase entre guillemets, ou mot jusqu'au prochain espace ou guillemet
+    fn value(&mut self) -> Result<&'a str, QueryError> {
+        let start = self.pos;
+        if self.eat('"') {
+            let phrase = self.take_while(|c| c != '"');
+            if !self.eat('"') {
+                return Err(self.error(start, "guillemet fermant manquant"));
+            }
+            return Ok(phrase);
+        }
+
+        let word = self.take_while(|c| !c.is_whitespace() && c != '"');
+        if word.is_empty() {
+            return Err(self.error(start, "valeur attendue"));
+        }
+        Ok(word)
+    }
+
+    fn qualifier(&mut self, name: &str, name_start: usize) -> Result<Predicate, QueryError> {
+        let date_field = match name {
+            "created" => Some(DateField::Created),
+            "updated" => Some(DateField::Updated),
+            "due" => Some(DateField::Due),
+            _ => None,
+        };
+
+        let comparison_start = self.pos;
+        let comparison = self.comparison();
+        if comparison.is_some() && date_field.is_none() && FIELDS.contains(&name) {
+            return Err(self.error(comparison_start, format!("{} n'accepte pas de comparateur", name)));
+        }
+
+        if let Some(field) = date_field {
+            let value_start = self.pos;
+            let value = self.value()?;
+            return Ok(Predicate::Date {
+                field,
+                comparison: comparison.unwrap_or(Comparison::Eq),
+                value: parse_date(value).ok_or_else(|| {
+                    self.error(value_start, format!("date invalide: {} (AAAA-MM-JJ ou RFC 3339)", value))
+                })?,
+            });
+        }
+
+        let value_start = self.pos;
+        match name {
+            "is" => match self.value()? {
+                "open" => Ok(Predicate::Completed(false)),
+                "done" => Ok(Predicate::Completed(true)),
+                other => Err(self.error(value_start, format!("is:{} inconnu (valeurs: open, done)", other))),
+            },
+            "title" => Ok(Predicate::Title(self.text()?)),
+            "lang" => {
+                let value = self.value()?;
+                TASK_LANGUAGES
+                    .iter()
+                    .find(|(code, _)| *code == value)
+                    .map(|&(code, _)| Predicate::Language(code))
+                    .ok_or_else(|| {
+                        let codes: Vec<&str> = TASK_LANGUAGES.iter().map(|(code, _)| *code).collect();
+                        self.error(value_start, format!("lang:{} inconnue (valeurs: {})", value, codes.join(", ")))
+                    })
+            }
+            "tag" => {
+                let value = self.value()?;
+                if !value.chars().all(is_tag_char) {
+                    return Err(self.error(
+                        value_start,
+                        format!("tag:{} invalide (lettres, chiffres, - et _)", value),
+                    ));
+                }
+                Ok(Predicate::Tag(value.to_lowercase()))
+            }
+            _ => Err(self.error(
+                name_start,
+                format!("champ inconnu: {} (champs: {})", name, FIELDS.join(", ")),
+            )),
+        }
+    }
+
+    fn comparison(&mut self) -> Option<Comparison> {
+        let comparison = if self.eat('<') {
+            if self.eat('=') { Comparison::Le } else { Comparison::Lt }
+        } else if self.eat('>') {
+            if self.eat('=') { Comparison::Ge } else { Comparison::Gt }
+        } else if self.eat('=') {
+            Comparison::Eq
+        } else {
+            return None;
+        };
+        Some(comparison)
+    }
+}
+
+fn parse_date(value: &str) -> Option<DateValue> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(DateValue::Day(date.and_hms_opt(0, 0, 0)?.and_utc()));
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|date| DateValue::Instant(date.with_timezone(&Utc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn parse(input: &str) -> Vec<Clause> {
+        TaskQuery::parse(input).unwrap().clauses
+    }
+
+    fn predicate(input: &str) -> Predicate {
+        let mut clauses = parse(input);
+        assert_eq!(clauses.len(), 1, "{}", input);
+        let clause = clauses.remove(0);
+        assert!(!clause.negated);
+        clause.predicate
+    }
+
+    fn error(input: &str) -> (usize, String) {
+        let error = TaskQuery::parse(input).unwrap_err();
+        (error.position, error.message)
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> DateValue {
+        DateValue::Day(Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn empty_query_has_no_clause() {
+        assert_eq!(parse(""), vec![]);
+        assert_eq!(parse("   "), vec![]);
+    }
+
+    #[test]
+    fn words_and_phrases_search_title_and_description() {
+        assert_eq!(predicate("courses"), Predicate::Text("%courses%".into()));
+        assert_eq!(predicate("\"liste de courses\""), Predicate::Text("%liste de courses%".into()));
+        // Un mot dont le préfixe n'est pas un nom de champ reste du texte
+        assert_eq!(predicate("10:30"), Predicate::Text("%10:30%".into()));
+    }
+
+    #[test]
+    fn is_qualifier() {
+        assert_eq!(predicate("is:open"), Predicate::Completed(false));
+        assert_eq!(predicate("is:done"), Predicate::Completed(true));
+    }
+
+    #[test]
+    fn title_qualifier() {
+        assert_eq!(predicate("title:courses"), Predicate::Title("%courses%".into()));
+        assert_eq!(predicate("title:\"de courses\""), Predicate::Title("%de courses%".into()));
+    }
+
+    #[test]
+    fn lang_qualifier() {
+        assert_eq!(predicate("lang:fr"), Predicate::Language("fr"));
+        assert_eq!(predicate("lang:en"), Predicate::Language("en"));
+    }
+
+    #[test]
+    fn tag_qualifier_is_lowercased() {
+        assert_eq!(predicate("tag:backend"), Predicate::Tag("backend".into()));
+        assert_eq!(predicate("tag:Front-End_2"), Predicate::Tag("front-end_2".into()));
+    }
+
+    #[test]
+    fn date_qualifiers() {
+        let cases = [
+            ("created:2024-01-01", DateField::Created, Comparison::Eq),
+            ("updated:<2024-01-01", DateField::Updated, Comparison::Lt),
+            ("due:<=2024-01-01", DateField::Due, Comparison::Le),
+            ("created:>2024-01-01", DateField::Created, Comparison::Gt),
+            ("due:>=2024-01-01", DateField::Due, Comparison::Ge),
+            ("updated:=2024-01-01", DateField::Updated, Comparison::Eq),
+        ];
+        for (input, field, comparison) in cases {
+            assert_eq!(
+                predicate(input),
+                Predicate::Date { field, comparison, value: day(2024, 1, 1) },
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn date_accepts_rfc3339_instant() {
+        assert_eq!(
+            predicate("due:<2026-11-01T10:00:00+02:00"),
+            Predicate::Date {
+                field: DateField::Due,
+                comparison: Comparison::Lt,
+                value: DateValue::Instant(Utc.with_ymd_and_hms(2026, 11, 1, 8, 0, 0).unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn negation() {
+        assert_eq!(
+            parse("-is:done -lait -\"en attente\" -tag:backend"),
+            vec![
+                Clause { negated: true, predicate: Predicate::Completed(true) },
+                Clause { negated: true, predicate: Predicate::Text("%lait%".into()) },
+                Clause { negated: true, predicate: Predicate::Text("%en attente%".into()) },
+                Clause { negated: true, predicate: Predicate::Tag("backend".into()) },
+            ]
+        );
+    }
+
+    #[test]
+    fn request_example() {
+        assert_eq!(
+            parse("is:open tag:backend due:<2026-11-01 \"release notes\""),
+            vec![
+                Clause { negated: false, predicate: Predicate::Completed(false) },
+                Clause { negated: false, predicate: Predicate::Tag("backend".into()) },
+                Clause {
+                    negated: false,
+                    predicate: Predicate::Date {
+                        field: DateField::Due,
+                        comparison: Comparison::Lt,
+                        value: day(2026, 11, 1),
+                    },
+                },
+                Clause { negated: false, predicate: Predicate::Text("%release notes%".into()) },
+            ]
+        );
+    }
+
+    #[test]
+    fn compiles_to_bound_conditions() {
+        let query = TaskQuery::parse("is:open tag:backend -due:2026-11-01 notes").unwrap();
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT 1 FROM tasks t WHERE TRUE");
+        query.push_conditions(&mut builder);
+        assert_eq!(
+            builder.sql(),
+            "SELECT 1 FROM tasks t WHERE TRUE AND (t.completed = $1) AND (t.tags @> $2) \
+             AND NOT (t.due_at IS NOT NULL AND t.due_at >= $3 AND t.due_at < $4) \
+             AND (t.title ILIKE $5 OR COALESCE(t.description, '') ILIKE $6)"
+        );
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        assert_eq!(error("\"non fermé").0, 1);
+        assert_eq!(error("is:open title:\"non fermé"), (15, "guillemet fermant manquant".into()));
+    }
+
+    #[test]
+    fn unknown_field() {
+        assert_eq!(
+            error("is:open prio:haute"),
+            (9, "champ inconnu: prio (champs: is, title, lang, tag, created, updated, due)".into())
+        );
+    }
+
+    #[test]
+    fn comparator_on_non_date_field() {
+        assert_eq!(error("is:<open"), (4, "is n'accepte pas de comparateur".into()));
+        assert_eq!(error("lang:fr tag:>=backend"), (13, "tag n'accepte pas de comparateur".into()));
+    }
+
+    #[test]
+    fn invalid_date() {
+        assert_eq!(
+            error("created:<demain"),
+            (10, "date invalide: demain (AAAA-MM-JJ ou RFC 3339)".into())
+        );
+        assert_eq!(error("due:2024-02-30").0, 5);
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert_eq!(error("is:maybe"), (4, "is:maybe inconnu (valeurs: open, done)".into()));
+        assert_eq!(error("lang:de"), (6, "lang:de inconnue (valeurs: fr, en)".into()));
+        assert_eq!(error("tag:a,b").0, 5);
+        assert_eq!(error("title:"), (7, "valeur attendue".into()));
+        assert_eq!(error("- lait"), (1, "terme attendu après -".into()));
+    }
+
+    #[test]
+    fn multibyte_positions_are_counted_in_characters() {
+        // « é » occupe deux octets : la position reste en caractères
+        assert_eq!(error("é é prio:x").0, 5);
+        assert_eq!(error("tâche \"déjà").0, 7);
+        assert_eq!(error("été created:hier").0, 13);
+        assert_eq!(predicate("title:été"), Predicate::Title("%été%".into()));
+        assert_eq!(predicate("tag:Été"), Predicate::Tag("été".into()));
+    }
+
+    #[test]
+    fn length_limit() {
+        assert!(TaskQuery::parse(&"é".repeat(MAX_QUERY_LENGTH)).is_ok());
+        assert_eq!(error(&"é".repeat(MAX_QUERY_LENGTH + 1)).0, MAX_QUERY_LENGTH + 1);
+    }
+}
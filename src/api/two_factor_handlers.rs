@@ -0,0 +1,446 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::api::auth_handlers::{auth_response, issue_refresh_token};
+use crate::api::dto::ApiResponse;
+use crate::audit::AuditEntry;
+use crate::api::session_handlers::start_session;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::lockout::LoginAttempt;
+use crate::middleware::auth::{ensure_account_active, AuthenticatedUser};
+use crate::middleware::client::ClientInfo;
+use crate::middleware::jwt::JwtKeys;
+use crate::models::{
+    AuthResponse, RecoveryCodesResponse, TwoFactorChallengeResponse, TwoFactorCodeRequest,
+    TwoFactorEnrollmentResponse, TwoFactorLoginRequest, User, UserTwoFactor,
+};
+use crate::utils::token::{hash_token, sign_payload, verify_payload};
+use crate::utils::totp;
+use crate::utils::validation::validate;
+
+pub const TWO_FACTOR_CHALLENGE: &str = "two_factor_challenge";
+
+// Contenu signé du challenge retourné par POST /auth/login
+#[derive(Serialize, Deserialize)]
+struct ChallengePayload {
+    jti: Uuid,
+    sub: Uuid,
+    purpose: String,
+    scope: String,
+    exp: i64,
+}
+
+/// Charge l'état de la double authentification d'un utilisateur
+pub async fn load_two_factor<'e, E>(executor: E, user_id: Uuid) -> AppResult<UserTwoFactor>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, UserTwoFactor>(
+        "SELECT email, password_hash, totp_secret, totp_enabled_at, totp_last_step
+         FROM users
+         WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Utilisateur non trouvé".to_string()))
+}
+
+/// Émet le challenge à échanger contre des tokens via POST /auth/login/2fa
+pub async fn issue_challenge(
+    db: &Database,
+    config: &AppConfig,
+    user_id: Uuid,
+    scope: &str,
+) -> AppResult<TwoFactorChallengeResponse> {
+    let jti = Uuid::new_v4();
+    let expires_at =
+        chrono::Utc::now() + chrono::Duration::seconds(config.two_factor_challenge_expiration as i64);
+
+    // Une nouvelle connexion invalide les challenges en attente
+    let mut tx = db.pool().begin().await?;
+
+    sqlx::query(
+        "UPDATE user_tokens SET used_at = NOW()
+         WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(TWO_FACTOR_CHALLENGE)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO user_tokens (id, user_id, purpose, expires_at)
+         VALUES ($1, $2, $3, $4)"
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(TWO_FACTOR_CHALLENGE)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let payload = ChallengePayload {
+        jti,
+        sub: user_id,
+        purpose: TWO_FACTOR_CHALLENGE.to_string(),
+        scope: scope.to_string(),
+        exp: expires_at.timestamp(),
+    };
+
+    Ok(TwoFactorChallengeResponse {
+        two_factor_required: true,
+        challenge_token: sign_payload(&payload, config.jwt_secret())?,
+        expires_in: config.two_factor_challenge_expiration,
+    })
+}
+
+// Vérifie un code TOTP, ou un code de récupération si `allow_recovery`,
+// et le consomme pour qu'il ne puisse pas être rejoué
+async fn verify_second_factor(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    config: &AppConfig,
+    user_id: Uuid,
+    state: &UserTwoFactor,
+    code: &str,
+    allow_recovery: bool,
+) -> AppResult<bool> {
+    let secret = match state.totp_secret.as_deref() {
+        Some(secret) => secret,
+        None => return Ok(false),
+    };
+
+    if totp::is_totp_code(code) {
+        let generator = totp::build(secret, &config.totp_issuer, &state.email)?;
+        let step = match totp::verify_code(&generator, code, state.totp_last_step) {
+            Some(step) => step,
+            None => return Ok(false),
+        };
+
+        // Condition sur le pas précédent : deux requêtes concurrentes ne
+        // peuvent pas consommer le même code
+        let result = sqlx::query(
+            "UPDATE users SET totp_last_step = $1
+             WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)"
+        )
+        .bind(step)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        return Ok(result.rows_affected() == 1);
+    }
+
+    if !allow_recovery {
+        return Ok(false);
+    }
+
+    let consumed = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE recovery_codes SET used_at = NOW()
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(hash_token(&totp::normalize_recovery_code(code)))
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    if consumed.is_some() {
+        tracing::info!(user_id = %user_id, "Code de récupération utilisé");
+    }
+    Ok(consumed.is_some())
+}
+
+// Remplace les codes de récupération de l'utilisateur et retourne les nouveaux en clair
+async fn replace_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+) -> AppResult<Vec<String>> {
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let codes = totp::generate_recovery_codes();
+    for code in &codes {
+        sqlx::query("INSERT INTO recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(hash_token(&totp::normalize_recovery_code(code)))
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(codes)
+}
+
+fn invalid_code() -> AppError {
+    AppError::BadRequest("Code de vérification invalide".to_string())
+}
+
+#[rocket::post("/auth/2fa/enroll")]
+pub async fn enroll(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<ApiResponse<TwoFactorEnrollmentResponse>>> {
+    user.require_interactive("de la double authentification")?;
+    let state = load_two_factor(db.pool(), user.user_id).await?;
+
+    if state.totp_enabled_at.is_some() {
+        return Err(AppError::Conflict("Double authentification déjà activée".to_string()));
+    }
+    if state.password_hash.is_none() {
+        return Err(AppError::BadRequest(
+            "La double authentification d'un compte externe est gérée par son fournisseur".to_string(),
+        ));
+    }
+
+    // Un enrôlement non confirmé est simplement remplacé
+    let secret = totp::generate_secret();
+    let generator = totp::build(&secret, &config.totp_issuer, &state.email)?;
+    let otpauth_uri = generator.get_url();
+    let qr_code_svg = qrcode::QrCode::new(otpauth_uri.as_bytes())
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Erreur lors de la génération du QR code: {}", e)))?
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(200, 200)
+        .build();
+
+    sqlx::query(
+        "UPDATE users SET totp_secret = $1, totp_last_step = NULL, updated_at = NOW()
+         WHERE id = $2"
+    )
+    .bind(&secret)
+    .bind(user.user_id)
+    .execute(db.pool())
+    .await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        TwoFactorEnrollmentResponse {
+            secret,
+            otpauth_uri,
+            qr_code_svg,
+        },
+        "Scannez le QR code puis confirmez avec un code".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/2fa/confirm", data = "<request>")]
+pub async fn confirm(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<TwoFactorCodeRequest>,
+) -> AppResult<Json<ApiResponse<RecoveryCodesResponse>>> {
+    user.require_interactive("de la double authentification")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+    let state = load_two_factor(&mut *tx, user.user_id).await?;
+
+    if state.totp_enabled_at.is_some() {
+        return Err(AppError::Conflict("Double authentification déjà activée".to_string()));
+    }
+    if state.totp_secret.is_none() {
+        return Err(AppError::BadRequest("Aucun enrôlement en cours".to_string()));
+    }
+    if !verify_second_factor(&mut tx, config, user.user_id, &state, &request_data.code, false).await? {
+        return Err(invalid_code());
+    }
+
+    sqlx::query("UPDATE users SET totp_enabled_at = NOW(), updated_at = NOW() WHERE id = $1")
+        .bind(user.user_id)
+        .execute(&mut *tx)
+        .await?;
+    let recovery_codes = replace_recovery_codes(&mut tx, user.user_id).await?;
+    AuditEntry::new("auth.2fa_enabled")
+        .by(&user)
+        .target("user", user.user_id)
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        RecoveryCodesResponse { recovery_codes },
+        "Double authentification activée".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/2fa/recovery-codes", data = "<request>")]
+pub async fn regenerate_recovery_codes(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<TwoFactorCodeRequest>,
+) -> AppResult<Json<ApiResponse<RecoveryCodesResponse>>> {
+    user.require_interactive("de la double authentification")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+    let state = load_two_factor(&mut *tx, user.user_id).await?;
+
+    if state.totp_enabled_at.is_none() {
+        return Err(AppError::BadRequest("Double authentification non activée".to_string()));
+    }
+    if !verify_second_factor(&mut tx, config, user.user_id, &state, &request_data.code, false).await? {
+        return Err(invalid_code());
+    }
+
+    let recovery_codes = replace_recovery_codes(&mut tx, user.user_id).await?;
+    AuditEntry::new("auth.2fa_recovery_codes_regenerated")
+        .by(&user)
+        .target("user", user.user_id)
+        .record(&mut *tx, &client)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        RecoveryCodesResponse { recovery_codes },
+        "Nouveaux codes de récupération générés".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/2fa/disable", data = "<request>")]
+pub async fn disable(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<TwoFactorCodeRequest>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    user.require_interactive("de la double authentification")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+    let state = load_two_factor(&mut *tx, user.user_id).await?;
+
+    if state.totp_enabled_at.is_none() {
+        return Err(AppError::BadRequest("Double authentification non activée".to_string()));
+    }
+    if !verify_second_factor(&mut tx, config, user.user_id, &state, &request_data.code, true).await? {
+        return Err(invalid_code());
+    }
+
+    sqlx::query(
+        "UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL, updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(user.user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+        .bind(user.user_id)
+        .execute(&mut *tx)
+        .await?;
+    AuditEntry::new("auth.2fa_disabled")
+        .by(&user)
+        .target("user", user.user_id)
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        (),
+        "Double authentification désactivée".to_string(),
+    )))
+}
+
+#[rocket::post("/auth/login/2fa", data = "<request>")]
+pub async fn login_two_factor(
+    db: &State<Database>,
+    config: &State<AppConfig>,
+    keys: &State<JwtKeys>,
+    client: ClientInfo,
+    request: Json<TwoFactorLoginRequest>,
+) -> AppResult<Json<ApiResponse<AuthResponse>>> {
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+    let invalid_challenge = || AppError::Authentication("Challenge invalide ou expiré".to_string());
+
+    let challenge = verify_payload::<ChallengePayload>(&request_data.challenge_token, config.jwt_secret())
+        .ok_or_else(invalid_challenge)?;
+    if challenge.purpose != TWO_FACTOR_CHALLENGE || challenge.exp <= chrono::Utc::now().timestamp() {
+        return Err(invalid_challenge());
+    }
+
+    let mut tx = db.pool().begin().await?;
+
+    // Verrouille le challenge : il n'est échangé qu'une fois
+    let pending = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM user_tokens
+         WHERE id = $1 AND user_id = $2 AND purpose = $3
+           AND used_at IS NULL AND expires_at > NOW()
+         FOR UPDATE"
+    )
+    .bind(challenge.jti)
+    .bind(challenge.sub)
+    .bind(TWO_FACTOR_CHALLENGE)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if pending.is_none() {
+        return Err(invalid_challenge());
+    }
+
+    let state = load_two_factor(&mut *tx, challenge.sub).await?;
+
+    // Les codes erronés comptent dans les échecs de connexion du compte
+    let attempt = LoginAttempt::new(&state.email, client.ip);
+    attempt.ensure_allowed(db).await?;
+    if !verify_second_factor(&mut tx, config, challenge.sub, &state, &request_data.code, true).await? {
+        AuditEntry::new("auth.login_failed")
+            .target("user", challenge.sub)
+            .record(db.pool(), &client)
+            .await?;
+        attempt.record_failure(db, config).await?;
+        return Err(AppError::Authentication("Code de vérification invalide".to_string()));
+    }
+
+    sqlx::query("UPDATE user_tokens SET used_at = NOW() WHERE id = $1")
+        .bind(challenge.jti)
+        .execute(&mut *tx)
+        .await?;
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, email, name, password_hash, email_verified_at, created_at, updated_at
+         FROM users
+         WHERE id = $1"
+    )
+    .bind(challenge.sub)
+    .fetch_one(&mut *tx)
+    .await?;
+    // Désactivé entre les deux étapes de la connexion
+    ensure_account_active(db, user.id).await?;
+
+    let session_id = start_session(&mut *tx, user.id, &client, config.refresh_token_expiration).await?;
+    let refresh_token = issue_refresh_token(
+        &mut *tx,
+        user.id,
+        session_id,
+        &challenge.scope,
+        config.refresh_token_expiration,
+    )
+    .await?;
+    AuditEntry::new("auth.login")
+        .by_user(user.id)
+        .target("session", session_id)
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+    attempt.record_success(db).await?;
+
+    Ok(Json(ApiResponse::success(
+        auth_response(db, config, keys, user, session_id, challenge.scope, refresh_token).await?,
+    )))
+}
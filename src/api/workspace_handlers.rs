@@ -0,0 +1,300 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use uuid::Uuid;
+use crate::api::dto::ApiResponse;
+use crate::audit::AuditEntry;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::client::ClientInfo;
+use crate::middleware::workspace::{WorkspaceMember, EDITOR, OWNER, WORKSPACE_ROLES};
+use crate::models::{
+    CreateWorkspaceRequest, UpdateMemberRoleRequest, Workspace, WorkspaceMemberResponse,
+    WorkspaceResponse, WorkspaceWithRole,
+};
+use crate::utils::validation::{parse_id, validate};
+
+#[rocket::post("/workspaces", data = "<request>")]
+pub async fn create_workspace(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    request: Json<CreateWorkspaceRequest>,
+) -> AppResult<Json<ApiResponse<WorkspaceResponse>>> {
+    user.require_interactive("des workspaces")?;
+    let request_data = request.into_inner();
+    validate(&request_data)?;
+
+    let mut tx = db.pool().begin().await?;
+
+    let workspace = sqlx::query_as::<_, Workspace>(
+        "INSERT INTO workspaces (id, name, created_by)
+         VALUES ($1, $2, $3)
+         RETURNING id, name, created_at, updated_at"
+    )
+    .bind(Uuid::new_v4())
+    .bind(&request_data.name)
+    .bind(user.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Le créateur en devient propriétaire
+    sqlx::query("INSERT INTO workspace_members (workspace_id, user_id, role) VALUES ($1, $2, $3)")
+        .bind(workspace.id)
+        .bind(user.user_id)
+        .bind(OWNER)
+        .execute(&mut *tx)
+        .await?;
+
+    AuditEntry::new("workspace.create")
+        .by(&user)
+        .target("workspace", workspace.id)
+        .after(&serde_json::json!({ "name": workspace.name }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        WorkspaceResponse::from(WorkspaceWithRole {
+            workspace,
+            role: OWNER.to_string(),
+        }),
+        "Workspace créé avec succès".to_string(),
+    )))
+}
+
+#[rocket::get("/workspaces")]
+pub async fn list_workspaces(
+    db: &State<Database>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<ApiResponse<Vec<WorkspaceResponse>>>> {
+    let workspaces = sqlx::query_as::<_, WorkspaceWithRole>(
+        "SELECT w.id, w.name, w.created_at, w.updated_at, m.role
+         FROM workspaces w
+         JOIN workspace_members m ON m.workspace_id = w.id
+         WHERE m.user_id = $1
+         ORDER BY w.name"
+    )
+    .bind(user.user_id)
+    .fetch_all(db.pool())
+    .await?;
+
+    let responses: Vec<WorkspaceResponse> = workspaces.into_iter().map(WorkspaceResponse::from).collect();
+    Ok(Json(ApiResponse::success(responses)))
+}
+
+// `id` est lu par le guard `WorkspaceMember`
+#[rocket::get("/workspaces/<_id>")]
+pub async fn get_workspace(
+    db: &State<Database>,
+    member: WorkspaceMember,
+    _id: &str,
+) -> AppResult<Json<ApiResponse<WorkspaceResponse>>> {
+    let workspace = sqlx::query_as::<_, Workspace>(
+        "SELECT id, name, created_at, updated_at FROM workspaces WHERE id = $1"
+    )
+    .bind(member.workspace_id)
+    .fetch_one(db.pool())
+    .await?;
+
+    Ok(Json(ApiResponse::success(WorkspaceResponse::from(WorkspaceWithRole {
+        workspace,
+        role: member.role,
+    }))))
+}
+
+#[rocket::get("/workspaces/<_id>/members")]
+pub async fn list_members(
+    db: &State<Database>,
+    member: WorkspaceMember,
+    _id: &str,
+) -> AppResult<Json<ApiResponse<Vec<WorkspaceMemberResponse>>>> {
+    let members = sqlx::query_as::<_, WorkspaceMemberResponse>(
+        "SELECT u.id AS user_id, u.email, u.name, m.role, m.created_at AS joined_at
+         FROM workspace_members m
+         JOIN users u ON u.id = m.user_id
+         WHERE m.workspace_id = $1
+         ORDER BY m.created_at"
+    )
+    .bind(member.workspace_id)
+    .fetch_all(db.pool())
+    .await?;
+
+    Ok(Json(ApiResponse::success(members)))
+}
+
+// Verrouille les membres du workspace et retourne leurs rôles, pour que les
+// vérifications sur le dernier propriétaire ne soient pas contournées par
+// des requêtes concurrentes
+async fn lock_members(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    workspace_id: Uuid,
+) -> AppResult<Vec<(Uuid, String)>> {
+    Ok(sqlx::query_as::<_, (Uuid, String)>(
+        "SELECT user_id, role FROM workspace_members WHERE workspace_id = $1 FOR UPDATE"
+    )
+    .bind(workspace_id)
+    .fetch_all(&mut **tx)
+    .await?)
+}
+
+fn last_owner_error() -> AppError {
+    AppError::Conflict("Le workspace doit conserver au moins un propriétaire".to_string())
+}
+
+#[rocket::put("/workspaces/<_id>/members/<user_id>", data = "<request>")]
+pub async fn update_member_role(
+    db: &State<Database>,
+    member: WorkspaceMember,
+    client: ClientInfo,
+    _id: &str,
+    user_id: &str,
+    request: Json<UpdateMemberRoleRequest>,
+) -> AppResult<Json<ApiResponse<String>>> {
+    member.user.require_interactive("des workspaces")?;
+    member.require_owner()?;
+    let user_id = parse_id(user_id)?;
+    let role = request.into_inner().role;
+    if !WORKSPACE_ROLES.contains(&role.as_str()) {
+        return Err(AppError::Validation(format!("Rôle inconnu: {}", role)));
+    }
+
+    let mut tx = db.pool().begin().await?;
+    let members = lock_members(&mut tx, member.workspace_id).await?;
+
+    let current_role = members
+        .iter()
+        .find(|(id, _)| *id == user_id)
+        .map(|(_, role)| role.clone())
+        .ok_or_else(|| AppError::NotFound(format!("Membre avec l'id {} non trouvé", user_id)))?;
+    let owners = members.iter().filter(|(_, role)| role == OWNER).count();
+    if current_role == OWNER && role != OWNER && owners == 1 {
+        return Err(last_owner_error());
+    }
+
+    sqlx::query("UPDATE workspace_members SET role = $1 WHERE workspace_id = $2 AND user_id = $3")
+        .bind(&role)
+        .bind(member.workspace_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    AuditEntry::new("workspace.member_role_update")
+        .by(&member.user)
+        .target("workspace", member.workspace_id)
+        .before(&serde_json::json!({ "user_id": user_id, "role": current_role }))?
+        .after(&serde_json::json!({ "user_id": user_id, "role": role }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        role,
+        "Rôle du membre mis à jour".to_string(),
+    )))
+}
+
+/// Retire un membre (ou quitte le workspace)
+///
+/// `policy` décide du sort des tâches dont il est propriétaire :
+/// - `transfer` (défaut) : transférées à `transfer_to`, ou à l'auteur de la
+///   suppression s'il retire un autre membre ;
+/// - `orphan` : conservées dans le workspace sans propriétaire.
+#[rocket::delete("/workspaces/<_id>/members/<user_id>?<policy>&<transfer_to>")]
+pub async fn remove_member(
+    db: &State<Database>,
+    member: WorkspaceMember,
+    client: ClientInfo,
+    _id: &str,
+    user_id: &str,
+    policy: Option<&str>,
+    transfer_to: Option<&str>,
+) -> AppResult<Json<ApiResponse<u64>>> {
+    member.user.require_interactive("des workspaces")?;
+    let user_id = parse_id(user_id)?;
+    let acting_user_id = member.user.user_id;
+    // Chacun peut quitter un workspace ; seuls les propriétaires retirent les autres
+    if user_id != acting_user_id {
+        member.require_owner()?;
+    }
+
+    let new_owner = match policy.unwrap_or("transfer") {
+        "transfer" => match transfer_to {
+            Some(id) => Some(parse_id(id)?),
+            None if user_id != acting_user_id => Some(acting_user_id),
+            None => {
+                return Err(AppError::BadRequest(
+                    "transfer_to est requis pour transférer ses propres tâches".to_string(),
+                ))
+            }
+        },
+        "orphan" => None,
+        other => return Err(AppError::BadRequest(format!("Politique inconnue: {}", other))),
+    };
+
+    let mut tx = db.pool().begin().await?;
+    let members = lock_members(&mut tx, member.workspace_id).await?;
+
+    let removed_role = members
+        .iter()
+        .find(|(id, _)| *id == user_id)
+        .map(|(_, role)| role.clone())
+        .ok_or_else(|| AppError::NotFound(format!("Membre avec l'id {} non trouvé", user_id)))?;
+    let owners = members.iter().filter(|(_, role)| role == OWNER).count();
+    if removed_role == OWNER && owners == 1 {
+        return Err(last_owner_error());
+    }
+
+    if let Some(new_owner) = new_owner {
+        // Le destinataire doit rester membre et pouvoir modifier les tâches
+        let can_receive = new_owner != user_id
+            && members
+                .iter()
+                .any(|(id, role)| *id == new_owner && (role == OWNER || role == EDITOR));
+        if !can_receive {
+            return Err(AppError::BadRequest(
+                "Les tâches doivent être transférées à un autre membre owner ou editor".to_string(),
+            ));
+        }
+    }
+
+    let reassigned = sqlx::query_scalar::<_, Uuid>(
+        "UPDATE tasks SET owner_id = $1 WHERE workspace_id = $2 AND owner_id = $3 RETURNING id"
+    )
+    .bind(new_owner)
+    .bind(member.workspace_id)
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    for task_id in &reassigned {
+        AuditEntry::new("task.transfer")
+            .by(&member.user)
+            .target("task", task_id)
+            .before(&serde_json::json!({ "owner_id": user_id }))?
+            .after(&serde_json::json!({ "owner_id": new_owner }))?
+            .record(&mut *tx, &client)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM workspace_members WHERE workspace_id = $1 AND user_id = $2")
+        .bind(member.workspace_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    AuditEntry::new("workspace.member_remove")
+        .by(&member.user)
+        .target("workspace", member.workspace_id)
+        .before(&serde_json::json!({ "user_id": user_id, "role": removed_role }))?
+        .record(&mut *tx, &client)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::success_with_message(
+        reassigned.len() as u64,
+        "Membre retiré du workspace".to_string(),
+    )))
+}
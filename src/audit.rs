@@ -0,0 +1,93 @@
+//! Journal d'audit
+//!
+//! Les événements de sécurité (connexions, double authentification, sessions,
+//! clés d'API, administration des comptes), les changements de membres des
+//! workspaces et chaque modification de tâche sont ajoutés à `audit_events`, avec l'auteur, la cible, l'identifiant de
+//! requête et l'état avant/après. La table est en ajout seul : un trigger
+//! refuse toute modification ou suppression.
+
+use serde::Serialize;
+use uuid::Uuid;
+use crate::errors::AppResult;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::client::ClientInfo;
+
+/// Événement à enregistrer dans le journal d'audit
+pub struct AuditEntry {
+    action: &'static str,
+    actor_id: Option<Uuid>,
+    api_key_id: Option<Uuid>,
+    target_type: Option<&'static str>,
+    target_id: Option<String>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    /// `action` suit la forme `domaine.verbe` (ex. `task.update`, `auth.login`)
+    pub fn new(action: &'static str) -> Self {
+        Self {
+            action,
+            actor_id: None,
+            api_key_id: None,
+            target_type: None,
+            target_id: None,
+            before: None,
+            after: None,
+        }
+    }
+
+    /// Auteur authentifié, avec la clé d'API utilisée le cas échéant
+    pub fn by(mut self, user: &AuthenticatedUser) -> Self {
+        self.actor_id = Some(user.user_id);
+        self.api_key_id = user.api_key_id;
+        self
+    }
+
+    /// Auteur identifié hors guard (connexion, lien reçu par email)
+    pub fn by_user(mut self, user_id: Uuid) -> Self {
+        self.actor_id = Some(user_id);
+        self
+    }
+
+    pub fn target(mut self, target_type: &'static str, target_id: impl ToString) -> Self {
+        self.target_type = Some(target_type);
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    pub fn before<T: Serialize>(mut self, value: &T) -> AppResult<Self> {
+        self.before = Some(serde_json::to_value(value)?);
+        Ok(self)
+    }
+
+    pub fn after<T: Serialize>(mut self, value: &T) -> AppResult<Self> {
+        self.after = Some(serde_json::to_value(value)?);
+        Ok(self)
+    }
+
+    /// Ajoute l'événement ; dans une transaction, il n'est conservé que si elle est validée
+    pub async fn record<'e, E>(self, executor: E, client: &ClientInfo) -> AppResult<()>
+    where
+        E: sqlx::PgExecutor<'e>,
+    {
+        sqlx::query(
+            "INSERT INTO audit_events
+                 (actor_id, api_key_id, action, target_type, target_id, request_id, ip_address, before, after)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        )
+        .bind(self.actor_id)
+        .bind(self.api_key_id)
+        .bind(self.action)
+        .bind(self.target_type)
+        .bind(&self.target_id)
+        .bind(&client.request_id)
+        .bind(client.ip.map(|ip| ip.to_string()))
+        .bind(&self.before)
+        .bind(&self.after)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
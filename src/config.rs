@@ -5,20 +5,51 @@
 
 use serde::Deserialize;
 use std::env;
+use std::fmt;
 
 /// Configuration principale de l'application
 ///
 /// Tous les paramètres de configuration sont chargés depuis les variables
 /// d'environnement et accessibles via cette structure.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct AppConfig {
     pub database_url: String,
     pub jwt_secret: String,
     pub jwt_expiration: u64,
+    pub jwt_algorithm: String,
+    pub jwt_keys_dir: Option<String>,
+    pub jwt_signing_kid: Option<String>,
+    pub oidc_issuer: Option<String>,
+    pub oidc_audience: Option<String>,
+    pub oidc_jwks_url: Option<String>,
+    pub oidc_jwks_file: Option<String>,
+    pub refresh_token_expiration: u64,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub password_reset_expiration: u64,
+    pub email_verification_expiration: u64,
+    pub two_factor_challenge_expiration: u64,
+    pub invitation_expiration: u64,
+    pub login_max_attempts: i32,
+    pub login_ip_max_attempts: i32,
+    pub login_attempt_window: u64,
+    pub login_lockout_base: u64,
+    pub login_lockout_max: u64,
+    pub totp_issuer: String,
+    pub app_base_url: String,
+    pub mail_transport: String,
+    pub mail_from: String,
+    pub mail_dir: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
     pub api_version: String,
     pub log_level: String,
     pub server_port: u16,
     pub server_host: String,
+    pub client_ip_header: Option<String>,
 }
 
 impl AppConfig {
@@ -43,6 +74,78 @@ impl AppConfig {
                 .unwrap_or("3600".to_string())
                 .parse()
                 .unwrap_or(3600),
+            jwt_algorithm: env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()),
+            jwt_keys_dir: env::var("JWT_KEYS_DIR").ok(),
+            jwt_signing_kid: env::var("JWT_SIGNING_KID").ok(),
+            oidc_issuer: env::var("OIDC_ISSUER").ok(),
+            oidc_audience: env::var("OIDC_AUDIENCE").ok(),
+            oidc_jwks_url: env::var("OIDC_JWKS_URL").ok(),
+            oidc_jwks_file: env::var("OIDC_JWKS_FILE").ok(),
+            refresh_token_expiration: env::var("REFRESH_TOKEN_EXPIRATION")
+                .unwrap_or("2592000".to_string())
+                .parse()
+                .unwrap_or(2592000),
+            // Recommandations OWASP pour Argon2id : 19 Mio, 2 itérations, 1 voie
+            argon2_memory_kib: env::var("ARGON2_MEMORY_KIB")
+                .unwrap_or("19456".to_string())
+                .parse()
+                .unwrap_or(19456),
+            argon2_iterations: env::var("ARGON2_ITERATIONS")
+                .unwrap_or("2".to_string())
+                .parse()
+                .unwrap_or(2),
+            argon2_parallelism: env::var("ARGON2_PARALLELISM")
+                .unwrap_or("1".to_string())
+                .parse()
+                .unwrap_or(1),
+            password_reset_expiration: env::var("PASSWORD_RESET_EXPIRATION")
+                .unwrap_or("3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            email_verification_expiration: env::var("EMAIL_VERIFICATION_EXPIRATION")
+                .unwrap_or("86400".to_string())
+                .parse()
+                .unwrap_or(86400),
+            two_factor_challenge_expiration: env::var("TWO_FACTOR_CHALLENGE_EXPIRATION")
+                .unwrap_or("300".to_string())
+                .parse()
+                .unwrap_or(300),
+            invitation_expiration: env::var("INVITATION_EXPIRATION")
+                .unwrap_or("604800".to_string())
+                .parse()
+                .unwrap_or(604800),
+            login_max_attempts: env::var("LOGIN_MAX_ATTEMPTS")
+                .unwrap_or("5".to_string())
+                .parse()
+                .unwrap_or(5),
+            login_ip_max_attempts: env::var("LOGIN_IP_MAX_ATTEMPTS")
+                .unwrap_or("20".to_string())
+                .parse()
+                .unwrap_or(20),
+            login_attempt_window: env::var("LOGIN_ATTEMPT_WINDOW")
+                .unwrap_or("900".to_string())
+                .parse()
+                .unwrap_or(900),
+            login_lockout_base: env::var("LOGIN_LOCKOUT_BASE")
+                .unwrap_or("60".to_string())
+                .parse()
+                .unwrap_or(60),
+            login_lockout_max: env::var("LOGIN_LOCKOUT_MAX")
+                .unwrap_or("3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            totp_issuer: env::var("TOTP_ISSUER").unwrap_or_else(|_| "RustAPI".to_string()),
+            app_base_url: env::var("APP_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            mail_transport: env::var("MAIL_TRANSPORT").unwrap_or_else(|_| "file".to_string()),
+            mail_from: env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@example.com".to_string()),
+            mail_dir: env::var("MAIL_DIR").unwrap_or_else(|_| "./mail".to_string()),
+            smtp_host: env::var("SMTP_HOST").ok(),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .unwrap_or(587),
+            smtp_username: env::var("SMTP_USERNAME").ok(),
+            smtp_password: env::var("SMTP_PASSWORD").ok(),
             api_version: env::var("API_VERSION").unwrap_or_else(|_| "v1".to_string()),
             log_level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
             server_port: env::var("PORT")
@@ -50,6 +153,9 @@ impl AppConfig {
                 .parse()
                 .unwrap_or(3000),
             server_host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            // En-tête portant l'adresse du client, à ne définir que derrière un
+            // proxy de confiance qui l'écrase ; sinon l'adresse du pair est utilisée
+            client_ip_header: env::var("CLIENT_IP_HEADER").ok().filter(|header| !header.is_empty()),
         })
     }
 
@@ -62,3 +168,49 @@ impl AppConfig {
     }
 }
 
+const REDACTED: &str = "***";
+
+// La configuration est journalisée au démarrage : les secrets y sont masqués
+impl fmt::Debug for AppConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppConfig")
+            .field("database_url", &REDACTED)
+            .field("jwt_secret", &REDACTED)
+            .field("jwt_expiration", &self.jwt_expiration)
+            .field("jwt_algorithm", &self.jwt_algorithm)
+            .field("jwt_keys_dir", &self.jwt_keys_dir)
+            .field("jwt_signing_kid", &self.jwt_signing_kid)
+            .field("oidc_issuer", &self.oidc_issuer)
+            .field("oidc_audience", &self.oidc_audience)
+            .field("oidc_jwks_url", &self.oidc_jwks_url)
+            .field("oidc_jwks_file", &self.oidc_jwks_file)
+            .field("refresh_token_expiration", &self.refresh_token_expiration)
+            .field("argon2_memory_kib", &self.argon2_memory_kib)
+            .field("argon2_iterations", &self.argon2_iterations)
+            .field("argon2_parallelism", &self.argon2_parallelism)
+            .field("password_reset_expiration", &self.password_reset_expiration)
+            .field("email_verification_expiration", &self.email_verification_expiration)
+            .field("two_factor_challenge_expiration", &self.two_factor_challenge_expiration)
+            .field("invitation_expiration", &self.invitation_expiration)
+            .field("login_max_attempts", &self.login_max_attempts)
+            .field("login_ip_max_attempts", &self.login_ip_max_attempts)
+            .field("login_attempt_window", &self.login_attempt_window)
+            .field("login_lockout_base", &self.login_lockout_base)
+            .field("login_lockout_max", &self.login_lockout_max)
+            .field("totp_issuer", &self.totp_issuer)
+            .field("app_base_url", &self.app_base_url)
+            .field("mail_transport", &self.mail_transport)
+            .field("mail_from", &self.mail_from)
+            .field("mail_dir", &self.mail_dir)
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("smtp_username", &self.smtp_username)
+            .field("smtp_password", &self.smtp_password.as_ref().map(|_| REDACTED))
+            .field("api_version", &self.api_version)
+            .field("log_level", &self.log_level)
+            .field("server_port", &self.server_port)
+            .field("server_host", &self.server_host)
+            .field("client_ip_header", &self.client_ip_header)
+            .finish()
+    }
+}
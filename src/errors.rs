@@ -6,6 +6,7 @@
 use rocket::http::Status;
 use rocket::response::{Responder, Response};
 use rocket::serde::json::Json;
+use rocket::request::Outcome;
 use rocket::{Request, response};
 use serde_json::json;
 use thiserror::Error;
@@ -31,6 +32,12 @@ pub enum AppError {
     #[error("Ressource non trouvée: {0}")]
     NotFound(String),
 
+    #[error("Conflit: {0}")]
+    Conflict(String),
+
+    #[error("Trop de requêtes: {message}")]
+    TooManyRequests { message: String, retry_after: u64 },
+
     #[error("Erreur interne du serveur: {0}")]
     Internal(#[from] anyhow::Error),
 
@@ -41,25 +48,66 @@ pub enum AppError {
     Serialization(#[from] serde_json::Error),
 }
 
+impl AppError {
+    /// Code de statut HTTP correspondant à l'erreur
+    pub fn status(&self) -> Status {
+        match self {
+            AppError::Database(_) => Status::InternalServerError,
+            AppError::Validation(_) => Status::BadRequest,
+            AppError::Authentication(_) => Status::Unauthorized,
+            AppError::Authorization(_) => Status::Forbidden,
+            AppError::NotFound(_) => Status::NotFound,
+            AppError::Conflict(_) => Status::Conflict,
+            AppError::TooManyRequests { .. } => Status::TooManyRequests,
+            AppError::Internal(_) => Status::InternalServerError,
+            AppError::BadRequest(_) => Status::BadRequest,
+            AppError::Serialization(_) => Status::BadRequest,
+        }
+    }
+}
+
+/// Message d'erreur d'un request guard, restitué par les catchers 400, 401 et 403
+///
+/// Rocket ne transmet pas l'erreur d'un guard au catcher : elle est donc
+/// conservée dans le cache local de la requête.
+pub struct GuardError(pub Option<String>);
+
+impl AppError {
+    /// Convertit l'erreur en échec de request guard
+    pub fn into_guard_outcome<S>(self, request: &Request<'_>) -> Outcome<S, AppError> {
+        let message = match self {
+            AppError::Authentication(ref msg)
+            | AppError::Authorization(ref msg)
+            | AppError::BadRequest(ref msg) => Some(msg.clone()),
+            _ => None,
+        };
+        request.local_cache(|| GuardError(message));
+        Outcome::Error((self.status(), self))
+    }
+}
+
 impl<'r> Responder<'r, 'static> for AppError {
     fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
-        let (status, error_message) = match self {
+        let status = self.status();
+        let error_message = match self {
             AppError::Database(ref e) => {
                 tracing::error!("Erreur de base de données: {}", e);
-                (Status::InternalServerError, self.to_string())
+                self.to_string()
             }
-            AppError::Validation(ref msg) => (Status::BadRequest, msg.clone()),
-            AppError::Authentication(ref msg) => (Status::Unauthorized, msg.clone()),
-            AppError::Authorization(ref msg) => (Status::Forbidden, msg.clone()),
-            AppError::NotFound(ref msg) => (Status::NotFound, msg.clone()),
+            AppError::Validation(ref msg) => msg.clone(),
+            AppError::Authentication(ref msg) => msg.clone(),
+            AppError::Authorization(ref msg) => msg.clone(),
+            AppError::NotFound(ref msg) => msg.clone(),
+            AppError::Conflict(ref msg) => msg.clone(),
+            AppError::TooManyRequests { ref message, .. } => message.clone(),
             AppError::Internal(ref e) => {
                 tracing::error!("Erreur interne: {}", e);
-                (Status::InternalServerError, "Une erreur interne s'est produite".to_string())
+                "Une erreur interne s'est produite".to_string()
             }
-            AppError::BadRequest(ref msg) => (Status::BadRequest, msg.clone()),
+            AppError::BadRequest(ref msg) => msg.clone(),
             AppError::Serialization(ref e) => {
                 tracing::error!("Erreur de sérialisation: {}", e);
-                (Status::BadRequest, "Erreur de format de données".to_string())
+                "Erreur de format de données".to_string()
             }
         };
 
@@ -68,18 +116,22 @@ impl<'r> Responder<'r, 'static> for AppError {
             "status": status.code,
         });
 
-        Response::build()
+        let mut response = Response::build();
+        response
             .status(status)
             .header(rocket::http::ContentType::JSON)
-            .sized_body(body.to_string().len(), std::io::Cursor::new(body.to_string()))
-            .ok()
+            .sized_body(body.to_string().len(), std::io::Cursor::new(body.to_string()));
+        if let AppError::TooManyRequests { retry_after, .. } = self {
+            response.raw_header("Retry-After", retry_after.to_string());
+        }
+        response.ok()
     }
 }
 
 pub type AppResult<T> = Result<T, AppError>;
 
 // Catchers pour les erreurs Rocket
-#[catch(404)]
+#[rocket::catch(404)]
 pub fn not_found(_req: &Request) -> Json<serde_json::Value> {
     Json(json!({
         "error": "Route non trouvée",
@@ -87,7 +139,7 @@ pub fn not_found(_req: &Request) -> Json<serde_json::Value> {
     }))
 }
 
-#[catch(500)]
+#[rocket::catch(500)]
 pub fn internal_error(_req: &Request) -> Json<serde_json::Value> {
     Json(json!({
         "error": "Une erreur interne s'est produite",
@@ -95,11 +147,30 @@ pub fn internal_error(_req: &Request) -> Json<serde_json::Value> {
     }))
 }
 
-#[catch(400)]
-pub fn bad_request(_req: &Request) -> Json<serde_json::Value> {
+#[rocket::catch(400)]
+pub fn bad_request(req: &Request) -> Json<serde_json::Value> {
+    let GuardError(message) = req.local_cache(|| GuardError(None));
     Json(json!({
-        "error": "Requête invalide",
+        "error": message.as_deref().unwrap_or("Requête invalide"),
         "status": 400,
     }))
 }
 
+
+#[rocket::catch(401)]
+pub fn unauthorized(req: &Request) -> Json<serde_json::Value> {
+    let GuardError(message) = req.local_cache(|| GuardError(None));
+    Json(json!({
+        "error": message.as_deref().unwrap_or("Authentification requise"),
+        "status": 401,
+    }))
+}
+
+#[rocket::catch(403)]
+pub fn forbidden(req: &Request) -> Json<serde_json::Value> {
+    let GuardError(message) = req.local_cache(|| GuardError(None));
+    Json(json!({
+        "error": message.as_deref().unwrap_or("Accès refusé"),
+        "status": 403,
+    }))
+}
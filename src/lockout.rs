@@ -0,0 +1,152 @@
+//! Protection contre la force brute sur la connexion
+//!
+//! Les échecs sont comptés par compte (email) et par adresse IP. Au-delà du
+//! seuil configuré, la clé est verrouillée pendant `LOGIN_LOCKOUT_BASE`
+//! secondes, durée doublée à chaque nouvel échec dans la limite de
+//! `LOGIN_LOCKOUT_MAX`. Les compteurs repartent de zéro après
+//! `LOGIN_ATTEMPT_WINDOW` secondes sans échec.
+//!
+//! L'adresse IP est celle du pair TCP, ou celle de l'en-tête `CLIENT_IP_HEADER`
+//! derrière un proxy de confiance : un en-tête choisi par le client ne permet
+//! pas de changer de compteur à chaque tentative.
+
+use std::net::IpAddr;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+
+const ACCOUNT: &str = "account";
+const IP: &str = "ip";
+
+/// Tentative de connexion identifiée par l'email saisi et l'adresse du client
+pub struct LoginAttempt {
+    email: String,
+    ip: Option<String>,
+}
+
+impl LoginAttempt {
+    pub fn new(email: &str, ip: Option<IpAddr>) -> Self {
+        Self {
+            email: email.trim().to_lowercase(),
+            ip: ip.map(|ip| ip.to_string()),
+        }
+    }
+
+    /// Refuse la tentative si le compte ou l'adresse IP est verrouillé
+    pub async fn ensure_allowed(&self, db: &Database) -> AppResult<()> {
+        let remaining = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT CEIL(EXTRACT(EPOCH FROM MAX(locked_until) - NOW()))::BIGINT
+             FROM login_attempts
+             WHERE locked_until > NOW()
+               AND ((kind = $1 AND key = $2) OR (kind = $3 AND key = $4))"
+        )
+        .bind(ACCOUNT)
+        .bind(&self.email)
+        .bind(IP)
+        .bind(&self.ip)
+        .fetch_one(db.pool())
+        .await?;
+
+        match remaining {
+            Some(seconds) => Err(too_many_attempts(seconds.max(1) as u64)),
+            None => Ok(()),
+        }
+    }
+
+    /// Enregistre un échec et retourne l'erreur 429 si un verrouillage en résulte
+    pub async fn record_failure(&self, db: &Database, config: &AppConfig) -> AppResult<()> {
+        let mut lockout = self
+            .increment(db, config, ACCOUNT, &self.email, config.login_max_attempts)
+            .await?;
+        if let Some(ip) = &self.ip {
+            let ip_lockout = self.increment(db, config, IP, ip, config.login_ip_max_attempts).await?;
+            lockout = lockout.max(ip_lockout);
+        }
+
+        match lockout {
+            Some(seconds) => {
+                tracing::warn!(email = %self.email, ip = ?self.ip, seconds, "Connexion verrouillée après des échecs répétés");
+                Err(too_many_attempts(seconds))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Remet à zéro le compteur du compte après une connexion réussie
+    ///
+    /// Le compteur de l'adresse IP est conservé : une connexion réussie sur
+    /// un compte ne doit pas lever la limite pour les autres comptes visés.
+    pub async fn record_success(&self, db: &Database) -> AppResult<()> {
+        unlock_account(db, &self.email).await
+    }
+
+    // Incrémente le compteur d'une clé et la verrouille au-delà du seuil
+    async fn increment(
+        &self,
+        db: &Database,
+        config: &AppConfig,
+        kind: &str,
+        key: &str,
+        max_attempts: i32,
+    ) -> AppResult<Option<u64>> {
+        let failed_count = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO login_attempts (kind, key, failed_count, last_failed_at)
+             VALUES ($1, $2, 1, NOW())
+             ON CONFLICT (kind, key) DO UPDATE SET
+                 failed_count = CASE
+                     WHEN login_attempts.last_failed_at < NOW() - make_interval(secs => $3) THEN 1
+                     ELSE login_attempts.failed_count + 1
+                 END,
+                 last_failed_at = NOW()
+             RETURNING failed_count"
+        )
+        .bind(kind)
+        .bind(key)
+        .bind(config.login_attempt_window as f64)
+        .fetch_one(db.pool())
+        .await?;
+
+        if failed_count < max_attempts {
+            return Ok(None);
+        }
+
+        // Backoff exponentiel : base, 2 x base, 4 x base... plafonné
+        let exponent = (failed_count - max_attempts).min(30) as u32;
+        let seconds = config
+            .login_lockout_base
+            .saturating_mul(1u64 << exponent)
+            .min(config.login_lockout_max);
+
+        sqlx::query(
+            "UPDATE login_attempts SET locked_until = NOW() + make_interval(secs => $3)
+             WHERE kind = $1 AND key = $2"
+        )
+        .bind(kind)
+        .bind(key)
+        .bind(seconds as f64)
+        .execute(db.pool())
+        .await?;
+
+        Ok(Some(seconds))
+    }
+}
+
+/// Lève le verrouillage et remet à zéro le compteur d'un compte
+pub async fn unlock_account(db: &Database, email: &str) -> AppResult<()> {
+    sqlx::query("DELETE FROM login_attempts WHERE kind = $1 AND key = $2")
+        .bind(ACCOUNT)
+        .bind(email.trim().to_lowercase())
+        .execute(db.pool())
+        .await?;
+    Ok(())
+}
+
+fn too_many_attempts(retry_after: u64) -> AppError {
+    AppError::TooManyRequests {
+        message: format!(
+            "Trop de tentatives de connexion, réessayez dans {} secondes",
+            retry_after
+        ),
+        retry_after,
+    }
+}
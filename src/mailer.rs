@@ -0,0 +1,190 @@
+//! Module d'envoi d'emails
+//!
+//! Ce module définit le trait `Mailer` et ses implémentations : SMTP pour la
+//! production, fichiers pour le développement local et mémoire pour les tests.
+//! Le transport est choisi via `MAIL_TRANSPORT` (`smtp`, `file` ou `memory`).
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use anyhow::Context;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Serialize;
+use crate::config::AppConfig;
+
+/// Email à envoyer
+#[derive(Debug, Clone, Serialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Transport d'emails
+#[rocket::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> anyhow::Result<()>;
+}
+
+/// Transport construit d'après la configuration
+pub struct ConfiguredMailer {
+    pub mailer: Arc<dyn Mailer>,
+    // Même transport sous son type concret avec `memory`, pour lire les emails envoyés
+    pub memory: Option<Arc<MemoryMailer>>,
+}
+
+/// Construit le transport configuré
+///
+/// # Erreurs
+/// Retourne une erreur si le transport est inconnu ou mal configuré.
+pub fn from_config(config: &AppConfig) -> anyhow::Result<ConfiguredMailer> {
+    let (mailer, memory): (Arc<dyn Mailer>, _) = match config.mail_transport.as_str() {
+        "smtp" => (Arc::new(SmtpMailer::new(config)?), None),
+        "file" => (Arc::new(FileMailer::new(&config.mail_dir)?), None),
+        "memory" => {
+            let memory = Arc::new(MemoryMailer::default());
+            (memory.clone(), Some(memory))
+        }
+        other => anyhow::bail!("Transport d'email inconnu: {}", other),
+    };
+    Ok(ConfiguredMailer { mailer, memory })
+}
+
+/// Envoi via un serveur SMTP (STARTTLS)
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &AppConfig) -> anyhow::Result<Self> {
+        let host = config
+            .smtp_host
+            .as_deref()
+            .context("SMTP_HOST est requis avec MAIL_TRANSPORT=smtp")?;
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?.port(config.smtp_port);
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config.mail_from.parse().context("MAIL_FROM invalide")?,
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> anyhow::Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse().context("Destinataire invalide")?)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)?;
+
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Écrit chaque email dans un fichier JSON (développement local)
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: &str) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Impossible de créer le répertoire d'emails {}", dir))?;
+        Ok(Self { dir: PathBuf::from(dir) })
+    }
+}
+
+#[rocket::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> anyhow::Result<()> {
+        let file_name = format!(
+            "{}-{}.json",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            uuid::Uuid::new_v4()
+        );
+        let path = self.dir.join(file_name);
+        rocket::tokio::fs::write(&path, serde_json::to_vec_pretty(&email)?).await?;
+
+        tracing::info!(to = %email.to, path = %path.display(), "Email écrit sur disque");
+        Ok(())
+    }
+}
+
+/// Nombre d'emails conservés par `MemoryMailer` ; les plus anciens sont oubliés
+const MEMORY_MAILER_CAPACITY: usize = 100;
+
+/// Conserve les derniers emails en mémoire sans les envoyer (tests, développement local)
+///
+/// Pour lire les emails envoyés, garder un `Arc<MemoryMailer>` et en passer un
+/// clone là où un `Arc<dyn Mailer>` est attendu ; l'application expose ce
+/// handle via `GET /admin/emails`.
+#[derive(Default)]
+pub struct MemoryMailer {
+    messages: Mutex<VecDeque<Email>>,
+}
+
+impl MemoryMailer {
+    /// Derniers emails envoyés, du plus ancien au plus récent
+    pub fn messages(&self) -> Vec<Email> {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+}
+
+#[rocket::async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, email: Email) -> anyhow::Result<()> {
+        let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        if messages.len() == MEMORY_MAILER_CAPACITY {
+            messages.pop_front();
+        }
+        messages.push_back(email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(n: usize) -> Email {
+        Email {
+            to: format!("user{}@example.com", n),
+            subject: "Sujet".to_string(),
+            body: "Corps".to_string(),
+        }
+    }
+
+    #[rocket::async_test]
+    async fn memory_mailer_keeps_messages_through_shared_handle() {
+        let memory = Arc::new(MemoryMailer::default());
+        let mailer: Arc<dyn Mailer> = memory.clone();
+        mailer.send(email(1)).await.unwrap();
+        mailer.send(email(2)).await.unwrap();
+
+        let to: Vec<String> = memory.messages().into_iter().map(|email| email.to).collect();
+        assert_eq!(to, ["user1@example.com", "user2@example.com"]);
+    }
+
+    #[rocket::async_test]
+    async fn memory_mailer_forgets_oldest_messages() {
+        let memory = MemoryMailer::default();
+        for n in 0..MEMORY_MAILER_CAPACITY + 5 {
+            memory.send(email(n)).await.unwrap();
+        }
+
+        let messages = memory.messages();
+        assert_eq!(messages.len(), MEMORY_MAILER_CAPACITY);
+        assert_eq!(messages[0].to, "user5@example.com");
+    }
+}
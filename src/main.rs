@@ -1,7 +1,11 @@
 mod api;
+mod audit;
 mod config;
 mod database;
 mod errors;
+mod lockout;
+mod mailer;
+mod maintenance;
 mod middleware;
 mod models;
 mod routes;
@@ -9,6 +13,8 @@ mod utils;
 
 use config::AppConfig;
 use database::Database;
+use middleware::jwt::JwtKeys;
+use middleware::oidc::OidcProvider;
 use middleware::{cors, logging, request_id};
 use rocket::fairing::AdHoc;
 use crate::api::{handlers, task_handlers};
@@ -39,17 +45,46 @@ async fn main() -> Result<(), rocket::Error> {
     let config = AppConfig::from_env().expect("Impossible de charger la configuration");
     tracing::info!("Configuration chargée: {:?}", config);
 
+    // Charger les clés de signature JWT
+    let jwt_keys = JwtKeys::from_config(&config).expect("Impossible de charger les clés JWT");
+
+    // Fournisseur OIDC externe (optionnel)
+    let oidc_provider = OidcProvider::from_config(&config)
+        .await
+        .expect("Impossible de configurer le fournisseur OIDC");
+
+    // Transport d'emails
+    let mailer = mailer::from_config(&config).expect("Impossible de configurer l'envoi d'emails");
+
     // Cloner les valeurs nécessaires pour AdHoc
     let database_url = config.database_url.clone();
     let server_port = config.server_port;
     let server_host = config.server_host.clone();
+    let client_ip_header = config.client_ip_header.clone();
     let config_clone = config.clone();
 
     // Lancer Rocket
-    let _rocket = rocket::build()
-        .configure(rocket::Config::figment()
-            .merge(("port", server_port))
-            .merge(("address", server_host.parse::<std::net::IpAddr>().unwrap())))
+    let mut rocket = rocket::build();
+    if let Some(oidc_provider) = oidc_provider {
+        rocket = rocket.manage(oidc_provider);
+    }
+    // Emails conservés en mémoire, consultables via `GET /admin/emails`
+    if let Some(memory_mailer) = mailer.memory {
+        rocket = rocket.manage(memory_mailer);
+    }
+
+    let mut figment = rocket::Config::figment()
+        .merge(("port", server_port))
+        .merge(("address", server_host.parse::<std::net::IpAddr>().unwrap()));
+    // Par défaut, Rocket croit l'en-tête X-Real-IP envoyé par le client :
+    // l'adresse utilisée (verrouillage de connexion, sessions, audit) serait falsifiable
+    figment = match client_ip_header {
+        Some(header) => figment.merge(("ip_header", header)),
+        None => figment.merge(("ip_header", false)),
+    };
+
+    let _rocket = rocket
+        .configure(figment)
         .attach(AdHoc::on_ignite("Database Setup", move |rocket| {
             let database_url = database_url.clone();
             let config = config_clone.clone();
@@ -64,6 +99,9 @@ async fn main() -> Result<(), rocket::Error> {
                 db.run_migrations().await.expect("Impossible d'exécuter les migrations");
                 tracing::info!("Migrations exécutées");
 
+                // Purge périodique des données d'authentification expirées
+                maintenance::spawn_purge_job(db.pool().clone());
+
                 rocket
                     .manage(db)
                     .manage(config)
@@ -72,7 +110,17 @@ async fn main() -> Result<(), rocket::Error> {
                     .attach(request_id::RequestIdFairing)
             }
         }))
+        .manage(jwt_keys)
+        .manage(mailer.mailer)
         .mount("/api", routes::get_routes())
+        .mount("/", routes::get_well_known_routes())
+        .register("/", rocket::catchers![
+            errors::bad_request,
+            errors::unauthorized,
+            errors::forbidden,
+            errors::not_found,
+            errors::internal_error,
+        ])
         .launch()
         .await?;
 
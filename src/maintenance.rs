@@ -0,0 +1,47 @@
+//! Tâches de maintenance périodiques
+//!
+//! Ce module purge régulièrement les données d'authentification devenues
+//! inutiles (tokens révoqués expirés, etc.).
+
+use sqlx::{Pool, Postgres};
+use std::time::Duration;
+
+/// Intervalle entre deux purges
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Requêtes de purge exécutées à chaque passage
+const PURGE_QUERIES: &[(&str, &str)] = &[
+    ("revoked_tokens", "DELETE FROM revoked_tokens WHERE expires_at < NOW()"),
+    ("user_tokens", "DELETE FROM user_tokens WHERE expires_at < NOW()"),
+    (
+        "sessions",
+        "DELETE FROM sessions WHERE COALESCE(revoked_at, expires_at) < NOW() - INTERVAL '30 days'",
+    ),
+    (
+        "login_attempts",
+        "DELETE FROM login_attempts WHERE GREATEST(last_failed_at, locked_until) < NOW() - INTERVAL '1 day'",
+    ),
+];
+
+/// Lance la purge périodique en tâche de fond
+pub fn spawn_purge_job(pool: Pool<Postgres>) {
+    rocket::tokio::spawn(async move {
+        let mut interval = rocket::tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            purge_expired(&pool).await;
+        }
+    });
+}
+
+async fn purge_expired(pool: &Pool<Postgres>) {
+    for (table, query) in PURGE_QUERIES {
+        match sqlx::query(query).execute(pool).await {
+            Ok(result) if result.rows_affected() > 0 => {
+                tracing::info!(table, rows = result.rows_affected(), "Purge des lignes expirées");
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!(table, "Erreur lors de la purge: {}", e),
+        }
+    }
+}
@@ -1,21 +1,67 @@
 use rocket::request::{FromRequest, Outcome, Request};
-use rocket::State;
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 use crate::errors::{AppError, AppResult};
-use crate::config::AppConfig;
+use crate::database::Database;
+use crate::middleware::jwt::JwtKeys;
+use crate::middleware::oidc::OidcProvider;
+use crate::middleware::scopes::SCOPES;
+use crate::utils::token::hash_token;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // Subject (user ID)
     pub exp: usize,  // Expiration time
     pub iat: usize,  // Issued at
+    pub jti: String, // Identifiant unique du token (révocation)
+    #[serde(default)]
+    pub roles: Vec<String>,
+    pub scope: String, // Scopes accordés, séparés par des espaces
+    // Session (connexion) à laquelle le token est rattaché
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+}
+
+/// Contenu accordé à un token lors de son émission
+pub struct TokenGrant {
+    pub roles: Vec<String>,
+    pub scope: String,
+    pub session_id: Option<Uuid>,
 }
 
 // Request Guard pour l'authentification
+#[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
-    pub user_id: String,
+    pub user_id: Uuid,
     pub claims: Claims,
+    // Renseigné lorsque la requête est authentifiée par clé d'API
+    pub api_key_id: Option<Uuid>,
+}
+
+impl AuthenticatedUser {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.claims.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.claims.scope.split_whitespace().any(|s| s == scope)
+    }
+
+    /// Réserve une opération sur le compte à un token de connexion complet
+    ///
+    /// Une clé d'API compromise, ou un token dont le scope a été restreint pour
+    /// une intégration, ne doit permettre ni de créer d'autres clés, ni de
+    /// toucher à la 2FA, aux sessions, au profil ou aux workspaces. `what`
+    /// complète le message d'erreur, ex. `"des sessions"`.
+    pub fn require_interactive(&self, what: &str) -> AppResult<()> {
+        if self.api_key_id.is_some() || !SCOPES.iter().all(|scope| self.has_scope(scope)) {
+            return Err(AppError::Authorization(format!(
+                "La gestion {} nécessite un token de connexion sans restriction de scope",
+                what
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[rocket::async_trait]
@@ -23,42 +69,134 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
     type Error = AppError;
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        // Récupérer le header Authorization
-        let auth_header = match request.headers().get_one("Authorization") {
-            Some(header) => header,
-            None => return Outcome::Error((rocket::http::Status::Unauthorized, AppError::Authentication("Token manquant".to_string()))),
-        };
-
-        if !auth_header.starts_with("Bearer ") {
-            return Outcome::Error((rocket::http::Status::Unauthorized, AppError::Authentication("Format de token invalide".to_string())));
+        // Authentification effectuée une seule fois par requête, même si
+        // plusieurs guards (scopes, rôles, workspace) en dépendent
+        let mut error = None;
+        let cached = request
+            .local_cache_async(async {
+                match authenticate(request).await {
+                    Ok(user) => Some(user),
+                    Err(e) => {
+                        error = Some(e);
+                        None
+                    }
+                }
+            })
+            .await;
+
+        match cached {
+            Some(user) => Outcome::Success(user.clone()),
+            None => error
+                .unwrap_or_else(|| AppError::Authentication("Token invalide ou expiré".to_string()))
+                .into_guard_outcome(request),
+        }
+    }
+}
+
+async fn authenticate(request: &Request<'_>) -> AppResult<AuthenticatedUser> {
+    let db = request
+        .rocket()
+        .state::<Database>()
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Base de données non disponible")))?;
+
+    // Clé d'API personnelle (scripts, CI)
+    if let Some(api_key) = request.headers().get_one("X-API-Key") {
+        let user = authenticate_api_key(db, api_key).await?;
+        ensure_account_active(db, user.user_id).await?;
+        return Ok(user);
+    }
+
+    // Récupérer le header Authorization
+    let auth_header = request
+        .headers()
+        .get_one("Authorization")
+        .ok_or_else(|| AppError::Authentication("Token manquant".to_string()))?;
+
+    if !auth_header.starts_with("Bearer ") {
+        return Err(AppError::Authentication("Format de token invalide".to_string()));
+    }
+
+    let token = auth_header.trim_start_matches("Bearer ");
+    let invalid = || AppError::Authentication("Token invalide ou expiré".to_string());
+
+    // Token émis par le fournisseur OIDC externe, s'il est configuré
+    let oidc = request.rocket().state::<OidcProvider>();
+    let (user_id, claims) = match oidc.filter(|oidc| oidc.is_issued_by(token)) {
+        Some(oidc) => {
+            let external = oidc.validate(token).await?;
+            let user_id = oidc.find_or_create_user(db, &external).await?;
+            let roles = load_roles(db, user_id).await?;
+            (user_id, external.into_local_claims(user_id, roles, token))
         }
+        None => {
+            // Récupérer les clés de vérification depuis l'état Rocket
+            let keys = request
+                .rocket()
+                .state::<JwtKeys>()
+                .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Clés JWT non disponibles")))?;
+
+            let claims = keys.decode::<Claims>(token).map_err(|_| invalid())?.claims;
+            let user_id = Uuid::parse_str(&claims.sub).map_err(|_| invalid())?;
+            (user_id, claims)
+        }
+    };
+
+    // Vérifier que le token n'a pas été révoqué (logout)
+    if is_token_revoked(db, &claims.jti).await? {
+        return Err(AppError::Authentication("Token révoqué".to_string()));
+    }
 
-        let token = auth_header.trim_start_matches("Bearer ");
-
-        // Récupérer la configuration depuis l'état Rocket
-        let config = match request.guard::<&State<AppConfig>>().await {
-            Outcome::Success(config) => config,
-            _ => return Outcome::Error((rocket::http::Status::InternalServerError, AppError::Internal(anyhow::anyhow!("Configuration non disponible")))),
-        };
-
-        let decoding_key = DecodingKey::from_secret(config.jwt_secret().as_ref());
-        let validation = Validation::new(Algorithm::HS256);
-
-        match decode::<Claims>(token, &decoding_key, &validation) {
-            Ok(token_data) => {
-                Outcome::Success(AuthenticatedUser {
-                    user_id: token_data.claims.sub.clone(),
-                    claims: token_data.claims,
-                })
-            }
-            Err(_) => Outcome::Error((rocket::http::Status::Unauthorized, AppError::Authentication("Token invalide ou expiré".to_string()))),
+    // Vérifier que la session n'a pas été fermée à distance
+    if let Some(session_id) = claims.sid {
+        if !is_session_active(db, session_id, user_id).await? {
+            return Err(AppError::Authentication("Session révoquée".to_string()));
         }
     }
+
+    ensure_account_active(db, user_id).await?;
+
+    Ok(AuthenticatedUser {
+        user_id,
+        claims,
+        api_key_id: None,
+    })
 }
 
-pub fn generate_token(user_id: &str, secret: &str, expiration: u64) -> AppResult<String> {
-    use jsonwebtoken::{encode, EncodingKey, Header};
+async fn authenticate_api_key(db: &Database, api_key: &str) -> AppResult<AuthenticatedUser> {
+    // Recherche et horodatage de la dernière utilisation en une seule requête
+    let key = sqlx::query_as::<_, (Uuid, Uuid, String)>(
+        "UPDATE api_keys SET last_used_at = NOW()
+         WHERE key_hash = $1 AND revoked_at IS NULL
+         RETURNING id, user_id, scope"
+    )
+    .bind(hash_token(api_key))
+    .fetch_optional(db.pool())
+    .await?;
+
+    let (api_key_id, user_id, scope) =
+        key.ok_or_else(|| AppError::Authentication("Clé d'API invalide ou révoquée".to_string()))?;
 
+    let now = chrono::Utc::now().timestamp() as usize;
+    let claims = Claims {
+        sub: user_id.to_string(),
+        exp: 0, // Les clés d'API n'expirent pas, elles sont révoquées
+        iat: now,
+        jti: format!("apikey:{}", api_key_id),
+        // Une clé d'API n'hérite pas des rôles de son propriétaire : elle est
+        // limitée à son scope et n'ouvre jamais les routes d'administration
+        roles: Vec::new(),
+        scope,
+        sid: None,
+    };
+
+    Ok(AuthenticatedUser {
+        user_id,
+        claims,
+        api_key_id: Some(api_key_id),
+    })
+}
+
+pub fn generate_token(user_id: &str, grant: TokenGrant, keys: &JwtKeys, expiration: u64) -> AppResult<String> {
     let now = chrono::Utc::now().timestamp() as usize;
     let exp = now + expiration as usize;
 
@@ -66,15 +204,131 @@ pub fn generate_token(user_id: &str, secret: &str, expiration: u64) -> AppResult
         sub: user_id.to_string(),
         exp,
         iat: now,
+        jti: Uuid::new_v4().to_string(),
+        roles: grant.roles,
+        scope: grant.scope,
+        sid: grant.session_id,
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_ref()),
+    keys.encode(&claims)
+}
+
+
+/// Ajoute le `jti` d'un token à la liste de révocation jusqu'à son expiration
+pub async fn revoke_token(db: &Database, claims: &Claims, user_id: Uuid) -> AppResult<()> {
+    let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0)
+        .unwrap_or_else(chrono::Utc::now);
+
+    sqlx::query(
+        "INSERT INTO revoked_tokens (jti, user_id, expires_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (jti) DO NOTHING"
+    )
+    .bind(&claims.jti)
+    .bind(user_id)
+    .bind(expires_at)
+    .execute(db.pool())
+    .await?;
+
+    Ok(())
+}
+
+/// Rôles attribués à un utilisateur
+pub async fn load_roles(db: &Database, user_id: Uuid) -> AppResult<Vec<String>> {
+    let roles = sqlx::query_scalar::<_, String>(
+        "SELECT role FROM user_roles WHERE user_id = $1 ORDER BY role"
     )
-    .map_err(|e| AppError::Internal(anyhow::anyhow!("Erreur lors de la génération du token: {}", e)))?;
+    .bind(user_id)
+    .fetch_all(db.pool())
+    .await?;
 
-    Ok(token)
+    Ok(roles)
 }
 
+/// Refuse les comptes désactivés par un administrateur (ou supprimés)
+pub async fn ensure_account_active(db: &Database, user_id: Uuid) -> AppResult<()> {
+    let active = sqlx::query_scalar::<_, bool>("SELECT disabled_at IS NULL FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db.pool())
+        .await?;
+
+    match active {
+        Some(true) => Ok(()),
+        Some(false) => Err(AppError::Authorization("Compte désactivé".to_string())),
+        None => Err(AppError::Authentication("Compte introuvable".to_string())),
+    }
+}
+
+async fn is_token_revoked(db: &Database, jti: &str) -> AppResult<bool> {
+    let revoked = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1)"
+    )
+    .bind(jti)
+    .fetch_one(db.pool())
+    .await?;
+
+    Ok(revoked)
+}
+
+// Vérifie que la session existe et n'est pas révoquée, et horodate son activité
+async fn is_session_active(db: &Database, session_id: Uuid, user_id: Uuid) -> AppResult<bool> {
+    let active = sqlx::query_scalar::<_, bool>(
+        "SELECT revoked_at IS NULL FROM sessions WHERE id = $1 AND user_id = $2"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(db.pool())
+    .await?
+    .unwrap_or(false);
+
+    if active {
+        // Au plus une écriture par minute et par session
+        sqlx::query(
+            "UPDATE sessions SET last_seen_at = NOW()
+             WHERE id = $1 AND last_seen_at < NOW() - INTERVAL '1 minute'"
+        )
+        .bind(session_id)
+        .execute(db.pool())
+        .await?;
+    }
+
+    Ok(active)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(scope: &str, api_key_id: Option<Uuid>) -> AuthenticatedUser {
+        AuthenticatedUser {
+            user_id: Uuid::nil(),
+            claims: Claims {
+                sub: Uuid::nil().to_string(),
+                exp: 0,
+                iat: 0,
+                jti: "test".to_string(),
+                roles: Vec::new(),
+                scope: scope.to_string(),
+                sid: None,
+            },
+            api_key_id,
+        }
+    }
+
+    #[test]
+    fn full_scope_token_is_interactive() {
+        assert!(user("tasks:read tasks:write", None).require_interactive("du compte").is_ok());
+    }
+
+    #[test]
+    fn narrowed_token_is_not_interactive() {
+        assert!(user("tasks:read", None).require_interactive("du compte").is_err());
+        assert!(user("", None).require_interactive("du compte").is_err());
+    }
+
+    #[test]
+    fn api_key_is_not_interactive() {
+        let key = user("tasks:read tasks:write", Some(Uuid::nil()));
+        assert!(key.require_interactive("du compte").is_err());
+    }
+}
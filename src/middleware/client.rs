@@ -0,0 +1,31 @@
+use std::convert::Infallible;
+use std::net::IpAddr;
+use rocket::request::{FromRequest, Outcome, Request};
+use uuid::Uuid;
+
+/// Longueur maximale conservée pour le User-Agent
+const MAX_USER_AGENT_LEN: usize = 255;
+
+/// Informations sur le client à l'origine de la requête (appareil, adresse)
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    // Identifiant attribué par `RequestIdFairing` (en-tête `x-request-id`)
+    pub request_id: String,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientInfo {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ClientInfo {
+            ip: request.client_ip(),
+            user_agent: request
+                .headers()
+                .get_one("User-Agent")
+                .map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect()),
+            request_id: request.local_cache(|| Uuid::new_v4().to_string()).clone(),
+        })
+    }
+}
@@ -15,7 +15,7 @@ impl Fairing for CorsFairing {
     async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
         response.set_header(rocket::http::Header::new("Access-Control-Allow-Origin", "*"));
         response.set_header(rocket::http::Header::new("Access-Control-Allow-Methods", "GET, POST, PUT, DELETE, OPTIONS"));
-        response.set_header(rocket::http::Header::new("Access-Control-Allow-Headers", "Content-Type, Authorization"));
+        response.set_header(rocket::http::Header::new("Access-Control-Allow-Headers", "Content-Type, Authorization, X-API-Key, X-Workspace-Id"));
         response.set_header(rocket::http::Header::new("Access-Control-Expose-Headers", "*"));
     }
 }
@@ -0,0 +1,198 @@
+//! Gestion des clés de signature JWT
+//!
+//! Supporte HS256 (secret partagé `JWT_SECRET`) ainsi que les algorithmes
+//! asymétriques RS256 et EdDSA. En mode asymétrique, les clés sont chargées
+//! depuis `JWT_KEYS_DIR` : chaque clé `<kid>` est composée d'un fichier
+//! `<kid>.pub.pem` (obligatoire) et d'un fichier `<kid>.key.pem` (clé privée,
+//! facultative). Seule la clé désignée par `JWT_SIGNING_KID` signe les
+//! nouveaux tokens ; les autres restent valides pour la vérification, ce qui
+//! permet la rotation des clés sans invalider les tokens en circulation.
+
+use std::collections::HashMap;
+use std::path::Path;
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::config::AppConfig;
+use crate::errors::{AppError, AppResult};
+
+/// Jeu de clés utilisé pour signer et vérifier les tokens
+pub struct JwtKeys {
+    algorithm: Algorithm,
+    signing_kid: Option<String>,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, DecodingKey>,
+    jwks: JwkSet,
+}
+
+impl JwtKeys {
+    /// Construit le jeu de clés depuis la configuration
+    ///
+    /// # Erreurs
+    /// Retourne une erreur si l'algorithme n'est pas supporté ou si les
+    /// fichiers de clés sont absents ou invalides.
+    pub fn from_config(config: &AppConfig) -> anyhow::Result<Self> {
+        let algorithm = match config.jwt_algorithm.as_str() {
+            "HS256" => Algorithm::HS256,
+            "RS256" => Algorithm::RS256,
+            "EdDSA" => Algorithm::EdDSA,
+            other => anyhow::bail!("Algorithme JWT non supporté: {}", other),
+        };
+
+        if algorithm == Algorithm::HS256 {
+            let mut decoding_keys = HashMap::new();
+            decoding_keys.insert(String::new(), DecodingKey::from_secret(config.jwt_secret().as_ref()));
+            return Ok(Self {
+                algorithm,
+                signing_kid: None,
+                encoding_key: EncodingKey::from_secret(config.jwt_secret().as_ref()),
+                decoding_keys,
+                jwks: JwkSet { keys: Vec::new() },
+            });
+        }
+
+        let keys_dir = config
+            .jwt_keys_dir
+            .as_deref()
+            .context("JWT_KEYS_DIR est requis pour les algorithmes asymétriques")?;
+        let signing_kid = config
+            .jwt_signing_kid
+            .clone()
+            .context("JWT_SIGNING_KID est requis pour les algorithmes asymétriques")?;
+
+        let mut decoding_keys = HashMap::new();
+        let mut jwks = JwkSet { keys: Vec::new() };
+
+        for entry in std::fs::read_dir(keys_dir)
+            .with_context(|| format!("Impossible de lire le répertoire de clés {}", keys_dir))?
+        {
+            let path = entry?.path();
+            let kid = match path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".pub.pem")) {
+                Some(kid) => kid.to_string(),
+                None => continue,
+            };
+
+            let pem = std::fs::read(&path)
+                .with_context(|| format!("Impossible de lire la clé publique {}", path.display()))?;
+            let decoding_key = match algorithm {
+                Algorithm::RS256 => DecodingKey::from_rsa_pem(&pem)?,
+                _ => DecodingKey::from_ed_pem(&pem)?,
+            };
+
+            jwks.keys.push(public_jwk(algorithm, &kid, &pem)?);
+            decoding_keys.insert(kid, decoding_key);
+        }
+
+        if !decoding_keys.contains_key(&signing_kid) {
+            anyhow::bail!("Clé publique introuvable pour JWT_SIGNING_KID={}", signing_kid);
+        }
+
+        let private_path = Path::new(keys_dir).join(format!("{}.key.pem", signing_kid));
+        let private_pem = std::fs::read(&private_path)
+            .with_context(|| format!("Impossible de lire la clé privée {}", private_path.display()))?;
+        let encoding_key = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&private_pem)?,
+            _ => EncodingKey::from_ed_pem(&private_pem)?,
+        };
+
+        tracing::info!(
+            algorithm = ?algorithm,
+            signing_kid = %signing_kid,
+            keys = decoding_keys.len(),
+            "Clés JWT chargées"
+        );
+
+        Ok(Self {
+            algorithm,
+            signing_kid: Some(signing_kid),
+            encoding_key,
+            decoding_keys,
+            jwks,
+        })
+    }
+
+    /// Signe des claims avec la clé active
+    pub fn encode<T: Serialize>(&self, claims: &T) -> AppResult<String> {
+        let mut header = Header::new(self.algorithm);
+        header.kid = self.signing_kid.clone();
+
+        encode(&header, claims, &self.encoding_key)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Erreur lors de la génération du token: {}", e)))
+    }
+
+    /// Vérifie un token avec la clé désignée par son `kid`
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> jsonwebtoken::errors::Result<TokenData<T>> {
+        let header = decode_header(token)?;
+        let kid = match self.algorithm {
+            Algorithm::HS256 => String::new(),
+            _ => header.kid.unwrap_or_default(),
+        };
+
+        let decoding_key = self
+            .decoding_keys
+            .get(&kid)
+            .ok_or_else(|| jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat))?;
+
+        decode::<T>(token, decoding_key, &Validation::new(self.algorithm))
+    }
+
+    /// Clés publiques au format JWKS (vide en HS256)
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+fn public_jwk(algorithm: Algorithm, kid: &str, pem: &[u8]) -> anyhow::Result<Jwk> {
+    let pem_str = std::str::from_utf8(pem).context("Clé publique PEM invalide")?;
+
+    let (key_algorithm, parameters) = match algorithm {
+        Algorithm::RS256 => {
+            let public_key = rsa::RsaPublicKey::from_public_key_pem(pem_str)
+                .or_else(|_| rsa::RsaPublicKey::from_pkcs1_pem(pem_str))
+                .with_context(|| format!("Clé publique RSA invalide pour {}", kid))?;
+            (
+                KeyAlgorithm::RS256,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+                }),
+            )
+        }
+        _ => {
+            // SubjectPublicKeyInfo Ed25519 : la clé brute occupe les 32 derniers octets
+            let der = pem::parse(pem).with_context(|| format!("Clé publique Ed25519 invalide pour {}", kid))?;
+            let contents = der.contents();
+            if contents.len() < 32 {
+                anyhow::bail!("Clé publique Ed25519 invalide pour {}", kid);
+            }
+            (
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(&contents[contents.len() - 32..]),
+                }),
+            )
+        }
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: parameters,
+    })
+}
@@ -1,7 +1,13 @@
 pub mod auth;
+pub mod client;
 pub mod cors;
+pub mod jwt;
 pub mod logging;
+pub mod oidc;
 pub mod request_id;
+pub mod roles;
+pub mod scopes;
+pub mod workspace;
 
 pub use cors::CorsFairing;
 pub use logging::LoggingFairing;
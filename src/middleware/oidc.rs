@@ -0,0 +1,336 @@
+//! Validation des tokens émis par un fournisseur OIDC externe (SSO)
+//!
+//! Activé lorsque `OIDC_ISSUER` est défini. Les clés publiques du fournisseur
+//! sont lues depuis un document JWKS, récupéré via `OIDC_JWKS_URL` ou chargé
+//! depuis `OIDC_JWKS_FILE` (tests, développement local). Chaque `sub` externe
+//! est associé à un `User` local, créé lors de la première connexion.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use rocket::tokio::sync::RwLock;
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::middleware::auth::Claims;
+use crate::middleware::scopes::{full_scope, SCOPES};
+use crate::utils::token::hash_token;
+
+/// Durée de validité du JWKS en cache
+const JWKS_TTL: Duration = Duration::from_secs(3600);
+
+/// Délai minimal entre deux rechargements déclenchés par un `kid` inconnu
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+enum JwksSource {
+    Url(String),
+    File(PathBuf),
+}
+
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Claims attendus dans un token OIDC
+#[derive(Debug, Deserialize)]
+pub struct OidcClaims {
+    pub sub: String,
+    pub exp: usize,
+    pub iat: Option<usize>,
+    pub jti: Option<String>,
+    pub scope: Option<String>,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+impl OidcClaims {
+    /// Convertit les claims externes en claims locaux pour `AuthenticatedUser`
+    ///
+    /// Le `jti` local est une empreinte de longueur fixe (64 caractères, la
+    /// taille de `revoked_tokens.jti`) du `jti` externe préfixé, pour ne jamais
+    /// entrer en collision avec ceux émis localement ; à défaut de `jti`,
+    /// l'empreinte du token en tient lieu.
+    /// Seuls les scopes connus localement sont conservés ; sans claim `scope`,
+    /// tous les scopes sont accordés.
+    pub fn into_local_claims(self, user_id: Uuid, roles: Vec<String>, token: &str) -> Claims {
+        let jti = match self.jti {
+            Some(jti) => hash_token(&format!("oidc:{}", jti)),
+            None => hash_token(token),
+        };
+
+        let scope = match self.scope {
+            Some(scope) => SCOPES
+                .iter()
+                .filter(|known| scope.split_whitespace().any(|s| s == **known))
+                .copied()
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => full_scope(),
+        };
+
+        Claims {
+            sub: user_id.to_string(),
+            exp: self.exp,
+            iat: self.iat.unwrap_or(0),
+            jti,
+            roles,
+            scope,
+            sid: None,
+        }
+    }
+}
+
+/// Fournisseur OIDC de confiance
+pub struct OidcProvider {
+    issuer: String,
+    audience: String,
+    source: JwksSource,
+    client: reqwest::Client,
+    jwks: RwLock<CachedJwks>,
+}
+
+impl OidcProvider {
+    /// Construit le fournisseur depuis la configuration et charge son JWKS
+    ///
+    /// Retourne `None` si `OIDC_ISSUER` n'est pas défini.
+    ///
+    /// # Erreurs
+    /// Retourne une erreur si la configuration est incomplète ou si le JWKS
+    /// initial ne peut pas être chargé.
+    pub async fn from_config(config: &AppConfig) -> anyhow::Result<Option<Self>> {
+        let issuer = match &config.oidc_issuer {
+            Some(issuer) => issuer.clone(),
+            None => return Ok(None),
+        };
+        let audience = config
+            .oidc_audience
+            .clone()
+            .context("OIDC_AUDIENCE est requis lorsque OIDC_ISSUER est défini")?;
+        let source = match (&config.oidc_jwks_url, &config.oidc_jwks_file) {
+            (Some(url), _) => JwksSource::Url(url.clone()),
+            (None, Some(file)) => JwksSource::File(PathBuf::from(file)),
+            (None, None) => anyhow::bail!("OIDC_JWKS_URL ou OIDC_JWKS_FILE est requis lorsque OIDC_ISSUER est défini"),
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let keys = load_jwks(&source, &client).await?;
+
+        tracing::info!(issuer = %issuer, keys = keys.keys.len(), "Fournisseur OIDC configuré");
+
+        Ok(Some(Self {
+            issuer,
+            audience,
+            source,
+            client,
+            jwks: RwLock::new(CachedJwks {
+                keys,
+                fetched_at: Instant::now(),
+            }),
+        }))
+    }
+
+    /// Indique si le token annonce cet émetteur (claim `iss` non vérifié)
+    ///
+    /// Sert uniquement à choisir le chemin de validation ; la signature et
+    /// l'émetteur sont ensuite vérifiés par `validate`.
+    pub fn is_issued_by(&self, token: &str) -> bool {
+        #[derive(Deserialize)]
+        struct Unverified {
+            iss: Option<String>,
+        }
+
+        token
+            .split('.')
+            .nth(1)
+            .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+            .and_then(|payload| serde_json::from_slice::<Unverified>(&payload).ok())
+            .and_then(|claims| claims.iss)
+            .is_some_and(|iss| iss == self.issuer)
+    }
+
+    /// Vérifie la signature, l'émetteur, l'audience et l'expiration du token
+    pub async fn validate(&self, token: &str) -> AppResult<OidcClaims> {
+        let invalid = || AppError::Authentication("Token invalide ou expiré".to_string());
+
+        let header = decode_header(token).map_err(|_| invalid())?;
+        let kid = header.kid.ok_or_else(invalid)?;
+        let jwk = self.find_key(&kid).await.ok_or_else(invalid)?;
+
+        // L'algorithme est imposé par la clé, jamais par l'en-tête du token
+        let algorithm = jwk
+            .common
+            .key_algorithm
+            .and_then(|alg| alg.to_string().parse::<Algorithm>().ok())
+            .unwrap_or(header.alg);
+        if matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) || algorithm != header.alg {
+            return Err(invalid());
+        }
+
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|_| invalid())?;
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+
+        decode::<OidcClaims>(token, &decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|_| invalid())
+    }
+
+    /// Retourne l'utilisateur local associé au `sub` externe, en le créant au besoin
+    pub async fn find_or_create_user(&self, db: &Database, claims: &OidcClaims) -> AppResult<Uuid> {
+        let existing = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM users WHERE external_issuer = $1 AND external_subject = $2"
+        )
+        .bind(&self.issuer)
+        .bind(&claims.sub)
+        .fetch_optional(db.pool())
+        .await?;
+        if let Some(user_id) = existing {
+            return Ok(user_id);
+        }
+
+        let email = claims
+            .email
+            .as_deref()
+            .map(|email| email.trim().to_lowercase())
+            .unwrap_or_else(|| format!("{}@oidc.invalid", claims.sub));
+        let name: String = claims
+            .name
+            .as_deref()
+            .or(claims.preferred_username.as_deref())
+            .unwrap_or(&claims.sub)
+            .chars()
+            .take(100)
+            .collect();
+        let now = chrono::Utc::now();
+
+        let created = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO users (id, email, name, password_hash, external_issuer, external_subject, created_at, updated_at)
+             VALUES ($1, $2, $3, NULL, $4, $5, $6, $7)
+             ON CONFLICT (external_issuer, external_subject) WHERE external_subject IS NOT NULL DO NOTHING
+             RETURNING id"
+        )
+        .bind(Uuid::new_v4())
+        .bind(&email)
+        .bind(&name)
+        .bind(&self.issuer)
+        .bind(&claims.sub)
+        .bind(now)
+        .bind(now)
+        .fetch_optional(db.pool())
+        .await
+        .map_err(|e| match e {
+            // Un compte local utilise déjà cet email : pas de liaison implicite
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                AppError::Conflict(format!("Un compte local existe déjà pour l'email {}", email))
+            }
+            e => AppError::Database(e),
+        })?;
+
+        match created {
+            Some(user_id) => {
+                tracing::info!(user_id = %user_id, sub = %claims.sub, "Utilisateur OIDC créé");
+                Ok(user_id)
+            }
+            // Créé entre-temps par une requête concurrente
+            None => Ok(sqlx::query_scalar::<_, Uuid>(
+                "SELECT id FROM users WHERE external_issuer = $1 AND external_subject = $2"
+            )
+            .bind(&self.issuer)
+            .bind(&claims.sub)
+            .fetch_one(db.pool())
+            .await?),
+        }
+    }
+
+    // Recherche une clé par `kid`, en rechargeant le JWKS s'il est périmé
+    // ou si la clé est inconnue (rotation côté fournisseur)
+    async fn find_key(&self, kid: &str) -> Option<Jwk> {
+        {
+            let cache = self.jwks.read().await;
+            let age = cache.fetched_at.elapsed();
+            match cache.keys.find(kid) {
+                Some(jwk) if age < JWKS_TTL => return Some(jwk.clone()),
+                None if age < JWKS_MIN_REFRESH => return None,
+                _ => {}
+            }
+        }
+
+        let mut cache = self.jwks.write().await;
+        // Un autre appel a pu recharger le JWKS pendant l'attente du verrou
+        if cache.fetched_at.elapsed() >= JWKS_MIN_REFRESH {
+            match load_jwks(&self.source, &self.client).await {
+                Ok(keys) => cache.keys = keys,
+                // On conserve les clés connues plutôt que de bloquer toute authentification
+                Err(e) => tracing::warn!("Impossible de recharger le JWKS OIDC: {}", e),
+            }
+            cache.fetched_at = Instant::now();
+        }
+        cache.keys.find(kid).cloned()
+    }
+}
+
+async fn load_jwks(source: &JwksSource, client: &reqwest::Client) -> anyhow::Result<JwkSet> {
+    match source {
+        JwksSource::Url(url) => Ok(client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<JwkSet>()
+            .await
+            .with_context(|| format!("JWKS invalide à l'adresse {}", url))?),
+        JwksSource::File(path) => {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Impossible de lire le JWKS {}", path.display()))?;
+            Ok(serde_json::from_str(&contents)
+                .with_context(|| format!("JWKS invalide dans {}", path.display()))?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(jti: Option<String>) -> OidcClaims {
+        OidcClaims {
+            sub: "external".to_string(),
+            exp: 0,
+            iat: None,
+            jti,
+            scope: None,
+            email: None,
+            name: None,
+            preferred_username: None,
+        }
+    }
+
+    #[test]
+    fn local_jti_fits_revoked_tokens_column() {
+        let long = "x".repeat(300);
+        for jti in [None, Some("abc".to_string()), Some(long)] {
+            let local = claims(jti).into_local_claims(Uuid::nil(), Vec::new(), "header.payload.signature");
+            assert_eq!(local.jti.len(), 64);
+        }
+    }
+
+    #[test]
+    fn local_jti_is_stable_and_distinct() {
+        let token = "header.payload.signature";
+        let a = claims(Some("a".to_string())).into_local_claims(Uuid::nil(), Vec::new(), token);
+        let again = claims(Some("a".to_string())).into_local_claims(Uuid::nil(), Vec::new(), token);
+        let b = claims(Some("b".to_string())).into_local_claims(Uuid::nil(), Vec::new(), token);
+        assert_eq!(a.jti, again.jti);
+        assert_ne!(a.jti, b.jti);
+    }
+}
@@ -0,0 +1,46 @@
+use std::marker::PhantomData;
+use rocket::request::{FromRequest, Outcome, Request};
+use crate::errors::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+
+/// Rôles pouvant être attribués à un utilisateur
+pub const ROLES: &[&str] = &[Admin::NAME];
+
+/// Rôle exigé par `RequireRole`
+pub trait Role: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+pub struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+// Request Guard exigeant un rôle, ex. `RequireRole<Admin>`
+pub struct RequireRole<R: Role> {
+    pub user: AuthenticatedUser,
+    _role: PhantomData<R>,
+}
+
+#[rocket::async_trait]
+impl<'r, R: Role> FromRequest<'r> for RequireRole<R> {
+    type Error = AppError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = match request.guard::<AuthenticatedUser>().await {
+            Outcome::Success(user) => user,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+
+        if !user.has_role(R::NAME) {
+            return AppError::Authorization(format!("Rôle requis: {}", R::NAME)).into_guard_outcome(request);
+        }
+
+        Outcome::Success(RequireRole {
+            user,
+            _role: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,118 @@
+use std::marker::PhantomData;
+use rocket::request::{FromRequest, Outcome, Request};
+use crate::errors::{AppError, AppResult};
+use crate::middleware::auth::AuthenticatedUser;
+
+/// Scopes pouvant être accordés à un token, dans leur ordre canonique
+pub const SCOPES: &[&str] = &[TasksRead::NAME, TasksWrite::NAME];
+
+/// Scope exigé par `RequireScope`
+pub trait Scope: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+pub struct TasksRead;
+
+impl Scope for TasksRead {
+    const NAME: &'static str = "tasks:read";
+}
+
+pub struct TasksWrite;
+
+impl Scope for TasksWrite {
+    const NAME: &'static str = "tasks:write";
+}
+
+/// Tous les scopes, au format du claim `scope` (séparés par des espaces)
+pub fn full_scope() -> String {
+    SCOPES.join(" ")
+}
+
+/// Valide un scope demandé et le normalise
+///
+/// Sans demande explicite, tous les scopes sont accordés. Sinon, chaque
+/// scope demandé doit appartenir à `allowed`.
+pub fn resolve_scope(requested: Option<&str>, allowed: &str) -> AppResult<String> {
+    let requested = match requested {
+        Some(requested) => requested,
+        None => return Ok(allowed.to_string()),
+    };
+
+    let requested: Vec<&str> = requested.split_whitespace().collect();
+    if requested.is_empty() {
+        return Err(AppError::Validation("Le scope demandé est vide".to_string()));
+    }
+
+    let allowed: Vec<&str> = allowed.split_whitespace().collect();
+    if let Some(scope) = requested.iter().find(|scope| !allowed.contains(scope)) {
+        return Err(AppError::Validation(format!("Scope non autorisé: {}", scope)));
+    }
+
+    Ok(SCOPES
+        .iter()
+        .filter(|scope| requested.contains(scope))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+// Request Guard exigeant un scope, ex. `RequireScope<TasksWrite>`
+pub struct RequireScope<S: Scope> {
+    pub user: AuthenticatedUser,
+    _scope: PhantomData<S>,
+}
+
+#[rocket::async_trait]
+impl<'r, S: Scope> FromRequest<'r> for RequireScope<S> {
+    type Error = AppError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = match request.guard::<AuthenticatedUser>().await {
+            Outcome::Success(user) => user,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+
+        if !user.has_scope(S::NAME) {
+            return AppError::Authorization(format!("Scope manquant: {}", S::NAME)).into_guard_outcome(request);
+        }
+
+        Outcome::Success(RequireScope {
+            user,
+            _scope: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_request_grants_everything_allowed() {
+        assert_eq!(resolve_scope(None, &full_scope()).unwrap(), "tasks:read tasks:write");
+        assert_eq!(resolve_scope(None, "tasks:read").unwrap(), "tasks:read");
+    }
+
+    #[test]
+    fn requested_scope_is_normalized() {
+        assert_eq!(
+            resolve_scope(Some("tasks:write  tasks:read tasks:write"), &full_scope()).unwrap(),
+            "tasks:read tasks:write"
+        );
+        assert_eq!(resolve_scope(Some("tasks:read"), &full_scope()).unwrap(), "tasks:read");
+    }
+
+    #[test]
+    fn scope_outside_allowed_is_refused() {
+        assert!(resolve_scope(Some("tasks:write"), "tasks:read").is_err());
+        assert!(resolve_scope(Some("tasks:read tasks:write"), "tasks:read").is_err());
+        assert!(resolve_scope(Some("admin"), &full_scope()).is_err());
+    }
+
+    #[test]
+    fn empty_request_is_refused() {
+        assert!(resolve_scope(Some(""), &full_scope()).is_err());
+        assert!(resolve_scope(Some("   "), &full_scope()).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use rocket::request::{FromRequest, Outcome, Request};
+use uuid::Uuid;
+use crate::database::Database;
+use crate::errors::{AppError, AppResult};
+use crate::middleware::auth::AuthenticatedUser;
+
+pub const OWNER: &str = "owner";
+pub const EDITOR: &str = "editor";
+pub const VIEWER: &str = "viewer";
+
+/// Rôles d'un membre dans un workspace
+pub const WORKSPACE_ROLES: &[&str] = &[OWNER, EDITOR, VIEWER];
+
+/// En-tête désignant le workspace actif pour les routes hors `/workspaces/<id>`
+pub const WORKSPACE_HEADER: &str = "X-Workspace-Id";
+
+// Request Guard résolvant le workspace actif et vérifiant l'appartenance
+//
+// Le workspace est lu dans le chemin (`/workspaces/<id>/...`) ou, à défaut,
+// dans l'en-tête `X-Workspace-Id` (ex. routes `/tasks`).
+pub struct WorkspaceMember {
+    pub user: AuthenticatedUser,
+    pub workspace_id: Uuid,
+    pub role: String,
+}
+
+impl WorkspaceMember {
+    /// Exige l'un des rôles donnés dans le workspace
+    pub fn require_role(&self, allowed: &[&str]) -> AppResult<()> {
+        if allowed.contains(&self.role.as_str()) {
+            Ok(())
+        } else {
+            Err(AppError::Authorization(format!(
+                "Rôle insuffisant dans le workspace (requis: {})",
+                allowed.join(" ou ")
+            )))
+        }
+    }
+
+    /// Création, modification et suppression de tâches
+    pub fn require_editor(&self) -> AppResult<()> {
+        self.require_role(&[OWNER, EDITOR])
+    }
+
+    /// Gestion des membres et des invitations
+    pub fn require_owner(&self) -> AppResult<()> {
+        self.require_role(&[OWNER])
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WorkspaceMember {
+    type Error = AppError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = match request.guard::<AuthenticatedUser>().await {
+            Outcome::Success(user) => user,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+
+        match resolve_membership(request, user).await {
+            Ok(member) => Outcome::Success(member),
+            Err(e) => e.into_guard_outcome(request),
+        }
+    }
+}
+
+async fn resolve_membership(request: &Request<'_>, user: AuthenticatedUser) -> AppResult<WorkspaceMember> {
+    let db = request
+        .rocket()
+        .state::<Database>()
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Base de données non disponible")))?;
+
+    let raw_id = match request.routed_segment(0) {
+        Some("workspaces") => request.routed_segment(1),
+        _ => request.headers().get_one(WORKSPACE_HEADER),
+    }
+    .ok_or_else(|| AppError::BadRequest(format!("Workspace manquant (en-tête {})", WORKSPACE_HEADER)))?;
+
+    let workspace_id = Uuid::parse_str(raw_id)
+        .map_err(|_| AppError::BadRequest(format!("ID de workspace invalide: {}", raw_id)))?;
+
+    // Même réponse que le workspace existe ou non
+    let role = sqlx::query_scalar::<_, String>(
+        "SELECT role FROM workspace_members WHERE workspace_id = $1 AND user_id = $2"
+    )
+    .bind(workspace_id)
+    .bind(user.user_id)
+    .fetch_optional(db.pool())
+    .await?
+    .ok_or_else(|| AppError::Authorization("Accès au workspace refusé".to_string()))?;
+
+    Ok(WorkspaceMember {
+        user,
+        workspace_id,
+        role,
+    })
+}
@@ -9,6 +9,10 @@ pub struct User {
     pub id: Uuid,
     pub email: String,
     pub name: String,
+    // Absent pour les comptes créés via le fournisseur OIDC
+    #[serde(skip_serializing)]
+    pub password_hash: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -28,6 +32,7 @@ pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
     pub name: String,
+    pub email_verified: bool,
     pub created_at: DateTime<Utc>,
 }
 
@@ -37,18 +42,105 @@ impl From<User> for UserResponse {
             id: user.id,
             email: user.email,
             name: user.name,
+            email_verified: user.email_verified_at.is_some(),
             created_at: user.created_at,
         }
     }
 }
 
+// Utilisateur et ses rôles (administration)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserWithRoles {
+    #[sqlx(flatten)]
+    pub user: User,
+    pub roles: Vec<String>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub password_reset_required: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminUserResponse {
+    #[serde(flatten)]
+    pub user: UserResponse,
+    pub roles: Vec<String>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub password_reset_required: bool,
+}
+
+impl From<UserWithRoles> for AdminUserResponse {
+    fn from(user: UserWithRoles) -> Self {
+        Self {
+            user: UserResponse::from(user.user),
+            roles: user.roles,
+            disabled_at: user.disabled_at,
+            password_reset_required: user.password_reset_required,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRolesRequest {
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LoginRequest {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 1))]
+    pub password: String,
+    // Sous-ensemble de scopes demandé (tous par défaut)
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RefreshTokenRequest {
+    #[validate(length(min = 1))]
+    pub refresh_token: String,
+    // Peut restreindre le scope accordé initialement, jamais l'étendre
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogoutRequest {
+    // Révoque aussi la famille de ce refresh token si fourni
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    pub scope: String,
+    pub refresh_token: String,
+    pub user: UserResponse,
+}
+
+// Refresh token opaque : seule son empreinte SHA-256 est stockée
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub scope: Option<String>,
+}
+
 // Modèle pour la gestion des tâches
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Task {
     pub id: Uuid,
+    pub workspace_id: Option<Uuid>,
+    pub owner_id: Option<Uuid>,
     pub title: String,
     pub description: Option<String>,
     pub completed: bool,
+    pub language: String,
+    pub tags: Vec<String>,
+    pub due_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -59,6 +151,10 @@ pub struct CreateTaskRequest {
     pub title: String,
     #[validate(length(max = 1000))]
     pub description: Option<String>,
+    // Langue du contenu pour la recherche : `fr` (défaut) ou `en`
+    pub language: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub due_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -68,14 +164,22 @@ pub struct UpdateTaskRequest {
     #[validate(length(max = 1000))]
     pub description: Option<String>,
     pub completed: Option<bool>,
+    pub language: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub due_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct TaskResponse {
     pub id: Uuid,
+    pub workspace_id: Option<Uuid>,
+    pub owner_id: Option<Uuid>,
     pub title: String,
     pub description: Option<String>,
     pub completed: bool,
+    pub language: String,
+    pub tags: Vec<String>,
+    pub due_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -84,12 +188,379 @@ impl From<Task> for TaskResponse {
     fn from(task: Task) -> Self {
         Self {
             id: task.id,
+            workspace_id: task.workspace_id,
+            owner_id: task.owner_id,
             title: task.title,
             description: task.description,
             completed: task.completed,
+            language: task.language,
+            tags: task.tags,
+            due_at: task.due_at,
             created_at: task.created_at,
             updated_at: task.updated_at,
         }
     }
 }
 
+// Tâche trouvée par la recherche plein texte
+#[derive(Debug, sqlx::FromRow)]
+pub struct TaskSearchHit {
+    #[sqlx(flatten)]
+    pub task: Task,
+    pub rank: f32,
+    pub title_highlight: String,
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskSearchResult {
+    #[serde(flatten)]
+    pub task: TaskResponse,
+    pub rank: f32,
+    // Titre et extraits de la description, termes trouvés entourés de <mark>
+    pub title_highlight: String,
+    pub snippet: Option<String>,
+}
+
+impl From<TaskSearchHit> for TaskSearchResult {
+    fn from(hit: TaskSearchHit) -> Self {
+        Self {
+            task: TaskResponse::from(hit.task),
+            rank: hit.rank,
+            title_highlight: hit.title_highlight,
+            snippet: hit.snippet,
+        }
+    }
+}
+
+
+// Clé d'API personnelle : seule son empreinte SHA-256 est stockée
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scope: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    // Sous-ensemble de scopes (tous par défaut)
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scope: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            key_prefix: key.key_prefix,
+            scope: key.scope,
+            created_at: key.created_at,
+            last_used_at: key.last_used_at,
+            revoked_at: key.revoked_at,
+        }
+    }
+}
+
+// Réponse à la création : la clé en clair n'est retournée qu'une seule fois
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub key: ApiKeyResponse,
+    pub api_key: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetConfirmRequest {
+    #[validate(length(min = 1))]
+    pub token: String,
+    #[validate(length(min = 8))]
+    pub password: String,
+}
+
+// Champs absents laissés inchangés
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateProfileRequest {
+    #[validate(length(min = 3, max = 100))]
+    pub name: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    // Exigé pour changer l'email d'un compte local
+    pub current_password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1))]
+    pub current_password: String,
+    #[validate(length(min = 8))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DeleteAccountRequest {
+    // Exigé pour un compte local
+    #[validate(length(min = 1))]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EmailVerificationConfirmRequest {
+    #[validate(length(min = 1))]
+    pub token: String,
+}
+
+// État de la double authentification d'un utilisateur
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserTwoFactor {
+    pub email: String,
+    pub password_hash: Option<String>,
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    pub totp_last_step: Option<i64>,
+}
+
+// Réponse à l'enrôlement : `otpauth_uri` est le contenu à encoder en QR code
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnrollmentResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub qr_code_svg: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCodeRequest {
+    // Code TOTP, ou code de récupération lorsque c'est accepté
+    #[validate(length(min = 1, max = 32))]
+    pub code: String,
+}
+
+// Les codes en clair ne sont retournés qu'une seule fois
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorLoginRequest {
+    #[validate(length(min = 1))]
+    pub challenge_token: String,
+    #[validate(length(min = 1, max = 32))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_in: u64,
+}
+
+// Réponse de POST /auth/login : tokens, ou challenge si la 2FA est activée
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    TwoFactorRequired(TwoFactorChallengeResponse),
+}
+
+// Session ouverte par une connexion (un appareil)
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    // Session du token ayant servi à la requête
+    pub current: bool,
+}
+
+impl SessionResponse {
+    pub fn new(session: Session, current_session_id: Option<Uuid>) -> Self {
+        Self {
+            current: current_session_id == Some(session.id),
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+// Espace de travail (organisation cliente) regroupant des tâches
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Workspace {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Workspace et rôle de l'utilisateur courant
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WorkspaceWithRole {
+    #[sqlx(flatten)]
+    pub workspace: Workspace,
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateWorkspaceRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<WorkspaceWithRole> for WorkspaceResponse {
+    fn from(entry: WorkspaceWithRole) -> Self {
+        Self {
+            id: entry.workspace.id,
+            name: entry.workspace.name,
+            role: entry.role,
+            created_at: entry.workspace.created_at,
+            updated_at: entry.workspace.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WorkspaceMemberResponse {
+    pub user_id: Uuid,
+    pub email: String,
+    pub name: String,
+    pub role: String,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WorkspaceInvitation {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateInvitationRequest {
+    #[validate(email)]
+    pub email: String,
+    pub role: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvitationResponse {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WorkspaceInvitation> for InvitationResponse {
+    fn from(invitation: WorkspaceInvitation) -> Self {
+        Self {
+            id: invitation.id,
+            workspace_id: invitation.workspace_id,
+            email: invitation.email,
+            role: invitation.role,
+            invited_by: invitation.invited_by,
+            expires_at: invitation.expires_at,
+            created_at: invitation.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct InvitationTokenRequest {
+    #[validate(length(min = 1))]
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMemberRoleRequest {
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ShareTaskRequest {
+    #[validate(email)]
+    pub email: String,
+    // `read` ou `write`
+    pub permission: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TaskShareResponse {
+    pub user_id: Uuid,
+    pub email: String,
+    pub name: String,
+    pub permission: String,
+    pub granted_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Événement du journal d'audit (en ajout seul)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub actor_id: Option<Uuid>,
+    pub api_key_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub request_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
@@ -1,14 +1,72 @@
-use crate::api::{handlers, task_handlers};
+use crate::api::{
+    account_handlers, admin_handlers, api_key_handlers, audit_handlers, auth_handlers, handlers,
+    invitation_handlers, profile_handlers, session_handlers, task_handlers, two_factor_handlers,
+    workspace_handlers,
+};
 
 pub fn get_routes() -> Vec<rocket::Route> {
     rocket::routes![
         handlers::health_check,
         handlers::root,
+        auth_handlers::register,
+        auth_handlers::login,
+        auth_handlers::refresh,
+        auth_handlers::logout,
+        two_factor_handlers::login_two_factor,
+        two_factor_handlers::enroll,
+        two_factor_handlers::confirm,
+        two_factor_handlers::regenerate_recovery_codes,
+        two_factor_handlers::disable,
+        account_handlers::request_password_reset,
+        account_handlers::confirm_password_reset,
+        account_handlers::request_email_verification,
+        account_handlers::confirm_email_verification,
+        profile_handlers::get_profile,
+        profile_handlers::update_profile,
+        profile_handlers::change_password,
+        profile_handlers::delete_profile,
+        session_handlers::list_sessions,
+        session_handlers::revoke_session,
+        session_handlers::revoke_other_sessions,
+        api_key_handlers::create_api_key,
+        api_key_handlers::list_api_keys,
+        api_key_handlers::revoke_api_key,
+        workspace_handlers::create_workspace,
+        workspace_handlers::list_workspaces,
+        workspace_handlers::get_workspace,
+        workspace_handlers::list_members,
+        workspace_handlers::update_member_role,
+        workspace_handlers::remove_member,
+        invitation_handlers::create_invitation,
+        invitation_handlers::list_invitations,
+        invitation_handlers::revoke_invitation,
+        invitation_handlers::accept_invitation,
+        invitation_handlers::decline_invitation,
         task_handlers::get_tasks,
+        task_handlers::search_tasks,
         task_handlers::get_task,
         task_handlers::create_task,
         task_handlers::update_task,
         task_handlers::delete_task,
+        task_handlers::list_task_shares,
+        task_handlers::share_task,
+        task_handlers::unshare_task,
+        admin_handlers::list_users,
+        admin_handlers::get_user,
+        admin_handlers::disable_user,
+        admin_handlers::enable_user,
+        admin_handlers::force_password_reset,
+        admin_handlers::delete_user,
+        admin_handlers::update_user_roles,
+        admin_handlers::unlock_user,
+        admin_handlers::list_all_tasks,
+        admin_handlers::list_sent_emails,
+        audit_handlers::list_audit_events,
     ]
 }
 
+
+// Routes montées à la racine (documents standards hors préfixe /api)
+pub fn get_well_known_routes() -> Vec<rocket::Route> {
+    rocket::routes![auth_handlers::jwks]
+}
@@ -1,10 +1,165 @@
-use bcrypt::{hash, verify, DEFAULT_COST};
+//! Hachage des mots de passe
+//!
+//! Les nouveaux mots de passe sont hachés en Argon2id (format PHC) avec les
+//! paramètres `ARGON2_*`. Les empreintes bcrypt existantes restent vérifiables
+//! et sont remplacées à la connexion suivante (voir `needs_rehash`).
 
-pub fn hash_password(password: &str) -> anyhow::Result<String> {
-    hash(password, DEFAULT_COST).map_err(|e| anyhow::anyhow!("Erreur lors du hachage: {}", e))
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use std::sync::OnceLock;
+use argon2::{Algorithm, Argon2, Params, Version};
+use crate::config::AppConfig;
+
+fn argon2(config: &AppConfig) -> anyhow::Result<Argon2<'static>> {
+    let params = Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        None,
+    )
+    .map_err(|e| anyhow::anyhow!("Paramètres Argon2 invalides: {}", e))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+fn is_bcrypt(hash: &str) -> bool {
+    hash.starts_with("$2a$") || hash.starts_with("$2b$") || hash.starts_with("$2y$")
 }
 
+pub fn hash_password(password: &str, config: &AppConfig) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    argon2(config)?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow::anyhow!("Erreur lors du hachage: {}", e))
+}
+
+/// Vérifie un mot de passe contre une empreinte Argon2 (PHC) ou bcrypt
 pub fn verify_password(password: &str, hash: &str) -> anyhow::Result<bool> {
-    verify(password, hash).map_err(|e| anyhow::anyhow!("Erreur lors de la vérification: {}", e))
+    if is_bcrypt(hash) {
+        return bcrypt::verify(password, hash)
+            .map_err(|e| anyhow::anyhow!("Erreur lors de la vérification: {}", e));
+    }
+
+    let parsed = PasswordHash::new(hash)
+        .map_err(|e| anyhow::anyhow!("Empreinte de mot de passe invalide: {}", e))?;
+    // Les paramètres sont lus dans l'empreinte elle-même
+    match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(anyhow::anyhow!("Erreur lors de la vérification: {}", e)),
+    }
 }
 
+/// Vérifie le mot de passe contre une empreinte factice aux paramètres actuels
+///
+/// Appelée quand le compte est inconnu ou sans mot de passe local : la
+/// connexion prend alors autant de temps que pour un compte existant, et le
+/// temps de réponse ne révèle pas l'existence du compte.
+pub fn verify_dummy_password(password: &str, config: &AppConfig) -> anyhow::Result<()> {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = match DUMMY_HASH.get() {
+        Some(hash) => hash,
+        None => {
+            let hash = hash_password("mot de passe factice", config)?;
+            DUMMY_HASH.get_or_init(|| hash)
+        }
+    };
+    verify_password(password, hash)?;
+    Ok(())
+}
+
+/// Indique si l'empreinte doit être recalculée avec l'algorithme et les paramètres actuels
+pub fn needs_rehash(hash: &str, config: &AppConfig) -> bool {
+    if is_bcrypt(hash) {
+        return true;
+    }
+
+    let parsed = match PasswordHash::new(hash) {
+        Ok(parsed) => parsed,
+        Err(_) => return true,
+    };
+    if parsed.algorithm != argon2::ARGON2ID_IDENT || parsed.version != Some(Version::V0x13.into()) {
+        return true;
+    }
+
+    match Params::try_from(&parsed) {
+        Ok(params) => {
+            params.m_cost() != config.argon2_memory_kib
+                || params.t_cost() != config.argon2_iterations
+                || params.p_cost() != config.argon2_parallelism
+        }
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Paramètres réduits pour garder les tests rapides
+    fn config() -> AppConfig {
+        let mut config = AppConfig::from_env().unwrap();
+        config.argon2_memory_kib = 1024;
+        config.argon2_iterations = 1;
+        config.argon2_parallelism = 1;
+        config
+    }
+
+    #[test]
+    fn bcrypt_verifies_and_needs_rehash() {
+        let hash = bcrypt::hash("correct horse", 4).unwrap();
+        assert!(verify_password("correct horse", &hash).unwrap());
+        assert!(!verify_password("battery staple", &hash).unwrap());
+        assert!(needs_rehash(&hash, &config()));
+    }
+
+    #[test]
+    fn current_argon2id_params_need_no_rehash() {
+        let config = config();
+        let hash = hash_password("correct horse", &config).unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(verify_password("correct horse", &hash).unwrap());
+        assert!(!verify_password("battery staple", &hash).unwrap());
+        assert!(!needs_rehash(&hash, &config));
+    }
+
+    #[test]
+    fn changed_params_need_rehash() {
+        let hash = hash_password("correct horse", &config()).unwrap();
+
+        let mut memory = config();
+        memory.argon2_memory_kib = 2048;
+        let mut iterations = config();
+        iterations.argon2_iterations = 2;
+        let mut parallelism = config();
+        parallelism.argon2_parallelism = 2;
+
+        for changed in [memory, iterations, parallelism] {
+            assert!(needs_rehash(&hash, &changed));
+        }
+        // L'ancienne empreinte reste vérifiable jusqu'au recalcul
+        assert!(verify_password("correct horse", &hash).unwrap());
+    }
+
+    #[test]
+    fn other_argon2_variant_needs_rehash() {
+        let config = config();
+        let params = Params::new(config.argon2_memory_kib, config.argon2_iterations, config.argon2_parallelism, None)
+            .unwrap();
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::new(Algorithm::Argon2i, Version::V0x13, params)
+            .hash_password(b"correct horse", &salt)
+            .unwrap()
+            .to_string();
+        assert!(verify_password("correct horse", &hash).unwrap());
+        assert!(needs_rehash(&hash, &config));
+    }
+
+    #[test]
+    fn malformed_phc_string_is_an_error() {
+        for hash in ["", "pas une empreinte", "$argon2id$v=19$m=abc$sel$empreinte"] {
+            assert!(verify_password("correct horse", hash).is_err(), "{:?}", hash);
+            assert!(needs_rehash(hash, &config()), "{:?}", hash);
+        }
+    }
+}
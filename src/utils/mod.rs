@@ -1,3 +1,5 @@
 pub mod hash;
+pub mod query;
+pub mod token;
+pub mod totp;
 pub mod validation;
-
@@ -0,0 +1,23 @@
+//! Lecture des paramètres d'URL utilisés pour filtrer les listes
+
+use chrono::{DateTime, Utc};
+use crate::errors::{AppError, AppResult};
+
+/// Date au format RFC 3339 (ex. `2024-01-01T00:00:00Z`)
+pub fn parse_timestamp(name: &str, value: Option<&str>) -> AppResult<Option<DateTime<Utc>>> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|date| date.with_timezone(&Utc))
+                .map_err(|_| AppError::BadRequest(format!("{} invalide (RFC 3339 attendu): {}", name, value)))
+        })
+        .transpose()
+}
+
+/// Motif `ILIKE` recherchant `text` comme sous-chaîne ; les jokers saisis
+/// (`%`, `_`) sont recherchés littéralement. `None` si le texte est vide.
+pub fn contains_pattern(text: Option<&str>) -> Option<String> {
+    text.map(str::trim).filter(|text| !text.is_empty()).map(|text| {
+        format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+    })
+}
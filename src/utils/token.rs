@@ -0,0 +1,44 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Génère un token opaque aléatoire (256 bits, encodé en base64url)
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Empreinte SHA-256 d'un token opaque, seule valeur stockée en base
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signe un payload JSON avec HMAC-SHA256
+///
+/// Format : `<payload base64url>.<signature base64url>`
+pub fn sign_payload<T: Serialize>(payload: &T, secret: &str) -> anyhow::Result<String> {
+    let encoded = URL_SAFE_NO_PAD.encode(serde_json::to_vec(payload)?);
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(encoded.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    Ok(format!("{}.{}", encoded, signature))
+}
+
+/// Vérifie la signature d'un token produit par `sign_payload` et retourne son payload
+pub fn verify_payload<T: DeserializeOwned>(token: &str, secret: &str) -> Option<T> {
+    let (encoded, signature) = token.split_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(encoded.as_bytes());
+    // Comparaison en temps constant
+    mac.verify_slice(&signature).ok()?;
+
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(encoded).ok()?).ok()
+}
@@ -0,0 +1,70 @@
+use rand::{Rng, RngCore};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// Durée d'un pas de temps TOTP (secondes)
+const STEP: u64 = 30;
+
+/// Pas acceptés de part et d'autre du pas courant (décalage d'horloge)
+const SKEW: i64 = 1;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// Sans caractères ambigus (0/o, 1/l/i)
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// Génère un nouveau secret TOTP (160 bits), encodé en base32
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+}
+
+/// Construit le générateur TOTP (SHA1, 6 chiffres, 30 s) d'un compte
+pub fn build(secret: &str, issuer: &str, account: &str) -> anyhow::Result<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| anyhow::anyhow!("Secret TOTP invalide: {}", e))?;
+
+    TOTP::new(Algorithm::SHA1, 6, 0, STEP, bytes, Some(issuer.to_string()), account.to_string())
+        .map_err(|e| anyhow::anyhow!("Paramètres TOTP invalides: {}", e))
+}
+
+/// Vérifie un code et retourne le pas de temps correspondant
+///
+/// Les pas inférieurs ou égaux à `last_step` sont refusés : un code déjà
+/// utilisé ne peut pas être rejoué.
+pub fn verify_code(totp: &TOTP, code: &str, last_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    let current = (chrono::Utc::now().timestamp() as u64 / STEP) as i64;
+
+    (current - SKEW..=current + SKEW)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| totp.check(code, *step as u64 * STEP))
+}
+
+/// Génère un jeu de codes de récupération au format `xxxx-xxxx`
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..8)
+                .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..4], &chars[4..])
+        })
+        .collect()
+}
+
+/// Forme canonique d'un code de récupération saisi (casse, tirets, espaces)
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Indique si la saisie a la forme d'un code TOTP plutôt que d'un code de récupération
+pub fn is_totp_code(code: &str) -> bool {
+    let code = code.trim();
+    code.len() == 6 && code.chars().all(|c| c.is_ascii_digit())
+}
@@ -1,3 +1,4 @@
+use uuid::Uuid;
 use validator::Validate;
 use crate::errors::{AppError, AppResult};
 
@@ -6,3 +7,8 @@ pub fn validate<T: Validate>(item: &T) -> AppResult<()> {
         .map_err(|e| AppError::Validation(e.to_string()))
 }
 
+
+/// Identifiant passé dans le chemin d'une route
+pub fn parse_id(id: &str) -> AppResult<Uuid> {
+    Uuid::parse_str(id).map_err(|_| AppError::BadRequest(format!("ID invalide: {}", id)))
+}